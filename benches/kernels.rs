@@ -0,0 +1,140 @@
+//! Compares the multiversioned audio kernels against the scalar code they replaced.
+//! Both sides write into the same preallocated buffers, so only the loops themselves are measured.
+//!
+//! Run with `cargo bench --bench kernels`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+#[path = "../src/audio/kernels.rs"]
+#[allow(dead_code)]
+mod kernels;
+
+/// 50ms of stereo audio, which is what pulse hands us per read
+const SAMPLES: usize = 48000 / 20 * 2;
+const BENCH_DURATION: Duration = Duration::from_secs(1);
+
+fn main() {
+    let samples: Vec<f32> = (0..SAMPLES).map(|i| (i as f32 * 0.01).sin()).collect();
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+    let mut out_samples = vec![0f32; SAMPLES];
+    let mut out_bytes = vec![0u8; SAMPLES * 4];
+    let mut left = vec![0f32; SAMPLES / 2];
+    let mut right = vec![0f32; SAMPLES / 2];
+
+    let scalar = measure(|| scalar_bytes_to_samples(black_box(&bytes), &mut out_samples));
+    let kernel = measure(|| kernels::bytes_to_samples(black_box(&bytes), &mut out_samples));
+    report("bytes to samples", scalar, kernel);
+
+    let scalar = measure(|| scalar_samples_to_bytes(black_box(&samples), &mut out_bytes));
+    let kernel = measure(|| kernels::samples_to_bytes(black_box(&samples), &mut out_bytes));
+    report("samples to bytes", scalar, kernel);
+
+    // The whole trip the audio used to take to have its volume normalized
+    let scalar = measure(|| scalar_apply_volume(black_box(&bytes), 0.5, &mut out_bytes));
+    let kernel = measure(|| {
+        kernels::bytes_to_samples(black_box(&bytes), &mut out_samples);
+        kernels::apply_gain(&mut out_samples, 0.5);
+        kernels::samples_to_bytes(&out_samples, &mut out_bytes);
+    });
+    report("volume", scalar, kernel);
+
+    let scalar = measure(|| scalar_deinterleave(black_box(&samples), &mut left, &mut right));
+    let kernel = measure(|| kernels::deinterleave(black_box(&samples), &mut left, &mut right));
+    report("deinterleave", scalar, kernel);
+
+    let scalar =
+        measure(|| scalar_interleave(black_box(&left), black_box(&right), &mut out_samples));
+    let kernel =
+        measure(|| kernels::interleave(black_box(&left), black_box(&right), &mut out_samples));
+    report("interleave", scalar, kernel);
+
+    let scalar = measure(|| {
+        black_box(scalar_peak(black_box(&samples)));
+    });
+    let kernel = measure(|| {
+        black_box(kernels::peak(black_box(&samples)));
+    });
+    report("peak", scalar, kernel);
+}
+
+fn report(name: &str, scalar_time: Duration, kernel_time: Duration) {
+    let speedup = scalar_time.as_secs_f64() / kernel_time.as_secs_f64();
+
+    println!(
+        "{:<18} scalar {:>9.2?}  kernel {:>9.2?}  {:>5.2}x",
+        name, scalar_time, kernel_time, speedup
+    );
+}
+
+/// Returns the average time of a single run
+fn measure(mut f: impl FnMut()) -> Duration {
+    // Warm up caches and the runtime dispatch
+    for _ in 0..100 {
+        f();
+    }
+
+    let start = Instant::now();
+    let mut iterations = 0u32;
+
+    while start.elapsed() < BENCH_DURATION {
+        f();
+        iterations += 1;
+    }
+
+    start.elapsed() / iterations
+}
+
+fn scalar_bytes_to_samples(bytes: &[u8], samples: &mut [f32]) {
+    let converted = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    for (sample, value) in samples.iter_mut().zip(converted) {
+        *sample = value;
+    }
+}
+
+fn scalar_samples_to_bytes(samples: &[f32], bytes: &mut [u8]) {
+    let converted = samples.iter().flat_map(|s| s.to_le_bytes());
+
+    for (byte, value) in bytes.iter_mut().zip(converted) {
+        *byte = value;
+    }
+}
+
+/// How `normalize_volume` used to go about it, one sample at a time
+fn scalar_apply_volume(bytes: &[u8], gain: f32, output: &mut [u8]) {
+    let converted = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .map(|s| s * gain)
+        .flat_map(|s| s.to_le_bytes());
+
+    for (byte, value) in output.iter_mut().zip(converted) {
+        *byte = value;
+    }
+}
+
+fn scalar_deinterleave(samples: &[f32], left: &mut [f32], right: &mut [f32]) {
+    for (i, frame) in samples.chunks_exact(2).enumerate() {
+        left[i] = frame[0];
+        right[i] = frame[1];
+    }
+}
+
+fn scalar_interleave(left: &[f32], right: &[f32], samples: &mut [f32]) {
+    for i in 0..left.len() {
+        samples[i * 2] = left[i];
+        samples[i * 2 + 1] = right[i];
+    }
+}
+
+fn scalar_peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0f32, |acc, x| if acc > x.abs() { acc } else { x.abs() })
+}
@@ -1,8 +1,11 @@
 use crate::{
-    audio::{pulse::PulseClientError, AudioSystem, Source},
+    audio::{
+        pulse::PulseClientError, AgcStats, AudioSystem, Band, BufferStats, Envelope, HistoryPoint,
+        Loudness, Sample, Source, MAX_DELAY_IN_SECONDS,
+    },
     dickcord::{self, DiscordSystem},
     interface::{Dashboard, Interface, Setup, Splash},
-    state::{Config, ReadOnlyConfig},
+    state::{Config, EncoderMode, MeterBallistics, ReadOnlyConfig, ResamplerQuality},
 };
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use parking_lot::Mutex;
@@ -13,6 +16,12 @@ use thiserror::Error;
 use tokio::runtime::{Builder, Runtime};
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+const MAX_DELAY_MS: u64 = (MAX_DELAY_IN_SECONDS * 1000.) as u64;
+
+/// The bitrate range Opus supports, in kbps
+const MIN_BITRATE_KBPS: u32 = 6;
+const MAX_BITRATE_KBPS: u32 = 510;
+const MAX_COMPLEXITY: u8 = 10;
 const LATEST_RELEASE_URL: &str =
     "https://api.github.com/repos/enitoni/pulseshitter/releases/latest";
 
@@ -56,6 +65,29 @@ pub enum AppAction {
     SetAudioSource(Source),
     ToggleScreenshareOnly,
     ToggleMeter,
+    ToggleSpectrum,
+    ToggleScope,
+    ToggleHistory,
+    ToggleCrossfade,
+    ToggleDucking,
+    ToggleAgc,
+    SetDelay(u64),
+    SetBitrate(Option<u32>),
+    ToggleLowDelay,
+    SetComplexity(u8),
+    ToggleFec,
+    SetResamplerQuality(ResamplerQuality),
+    SetMeterBallistics(MeterBallistics),
+    ToggleEffect(usize),
+    /// Sets a parameter of the effect at the index, by name
+    SetEffectParameter(usize, String, f32),
+    /// Moves the effect at the index up or down the chain
+    MoveEffect(usize, isize),
+    /// Stops sending audio without leaving the call or deselecting the source
+    TogglePause,
+    /// Starts measuring the integrated loudness from scratch
+    ResetLoudness,
+    ResetClips,
     StopStream,
     RedoSetup,
     Exit,
@@ -64,6 +96,8 @@ pub enum AppAction {
 #[allow(clippy::large_enum_variant)]
 pub enum AppEvent {
     DiscordStateUpdate(dickcord::State),
+    /// Whether someone the stream should be lowered for is talking
+    DuckingStateUpdate(bool),
     Action(AppAction),
 }
 
@@ -109,12 +143,28 @@ impl App {
         if let Err(err) = result {
             eprintln!("Render error: {}", err)
         }
+
+        self.save_session();
+    }
+
+    /// Writes a summary of the session, so it can be looked back on later
+    fn save_session(&self) {
+        let summary = self.audio.session_summary();
+
+        if summary.is_empty() {
+            return;
+        }
+
+        if let Err(err) = summary.save() {
+            eprintln!("Session summary save failed: {}", err)
+        }
     }
 
     fn restore(&self) {
         let config = Config::restore();
 
         if let Some(config) = config {
+            self.audio.set_config(config.effective_audio());
             self.discord.connect(&config);
             self.interface.set_view(Dashboard::new(self.context()))
         } else {
@@ -146,6 +196,7 @@ impl App {
         match event {
             AppEvent::Action(action) => self.handle_action(action),
             AppEvent::DiscordStateUpdate(new_state) => self.handle_discord_state_update(new_state),
+            AppEvent::DuckingStateUpdate(is_ducked) => self.audio.set_ducked(is_ducked),
         }
     }
 
@@ -184,11 +235,120 @@ impl App {
                     config.show_meter = !config.show_meter;
                 });
             }
+            AppAction::ToggleSpectrum => {
+                self.edit_config(|config| {
+                    config.show_spectrum = !config.show_spectrum;
+                });
+            }
+            AppAction::ToggleScope => {
+                self.edit_config(|config| {
+                    config.show_scope = !config.show_scope;
+                });
+            }
+            AppAction::ToggleHistory => {
+                self.edit_config(|config| {
+                    config.show_history = !config.show_history;
+                });
+            }
+            AppAction::ToggleCrossfade => {
+                self.edit_config(|config| {
+                    config.audio.crossfade = !config.audio.crossfade;
+                });
+            }
+            AppAction::ToggleDucking => {
+                self.edit_config(|config| {
+                    config.audio.ducking.enabled = !config.audio.ducking.enabled;
+                });
+            }
+            AppAction::ToggleAgc => {
+                self.edit_config(|config| {
+                    config.audio.agc.enabled = !config.audio.agc.enabled;
+                });
+            }
+            AppAction::SetDelay(delay_ms) => {
+                self.edit_config(|config| {
+                    config.edit_audio(|audio| audio.delay_ms = delay_ms.min(MAX_DELAY_MS));
+                });
+            }
+            AppAction::SetBitrate(bitrate_kbps) => {
+                self.edit_config(|config| {
+                    config.audio.encoder.bitrate_kbps =
+                        bitrate_kbps.map(|b| b.clamp(MIN_BITRATE_KBPS, MAX_BITRATE_KBPS));
+                });
+            }
+            AppAction::ToggleLowDelay => {
+                self.edit_config(|config| {
+                    config.audio.encoder.mode = match config.audio.encoder.mode {
+                        EncoderMode::Audio => EncoderMode::LowDelay,
+                        EncoderMode::LowDelay => EncoderMode::Audio,
+                    };
+                });
+            }
+            AppAction::SetComplexity(complexity) => {
+                self.edit_config(|config| {
+                    config.audio.encoder.complexity = complexity.min(MAX_COMPLEXITY);
+                });
+            }
+            AppAction::ToggleFec => {
+                self.edit_config(|config| {
+                    config.audio.encoder.fec = !config.audio.encoder.fec;
+                });
+            }
+            AppAction::SetResamplerQuality(quality) => {
+                self.edit_config(|config| {
+                    config.audio.resampler_quality = quality;
+                });
+            }
+            AppAction::SetMeterBallistics(ballistics) => {
+                self.edit_config(|config| {
+                    config.audio.meter_ballistics = ballistics;
+                });
+            }
+            AppAction::ToggleEffect(index) => {
+                self.edit_config(|config| {
+                    config.edit_audio(|audio| {
+                        if let Some(effect) = audio.effects.get_mut(index) {
+                            effect.bypassed = !effect.bypassed;
+                        }
+                    });
+                });
+            }
+            AppAction::SetEffectParameter(index, name, value) => {
+                self.edit_config(|config| {
+                    config.edit_audio(|audio| {
+                        if let Some(effect) = audio.effects.get_mut(index) {
+                            effect.parameters.insert(name, value);
+                        }
+                    });
+                });
+            }
+            AppAction::MoveEffect(index, amount) => {
+                self.edit_config(|config| {
+                    let effects = &mut config.audio.effects;
+                    let new_index = index.saturating_add_signed(amount);
+
+                    if index < effects.len() && new_index < effects.len() {
+                        effects.swap(index, new_index);
+                    }
+                });
+            }
             AppAction::SetAudioSource(source) => {
+                self.edit_config(|config| config.active_profile = Some(source.application()));
+
+                self.set_paused(false);
                 self.audio.select(Some(source.clone()));
                 self.discord.announce_source_streaming(Some(source));
             }
+            AppAction::TogglePause => {
+                if self.audio.current_source().is_some() {
+                    self.set_paused(!self.audio.is_paused());
+                }
+            }
+            AppAction::ResetLoudness => self.audio.reset_loudness(),
+            AppAction::ResetClips => self.audio.reset_clips(),
             AppAction::StopStream => {
+                self.edit_config(|config| config.active_profile = None);
+                self.set_paused(false);
                 self.audio.select(None);
                 self.discord.announce_source_streaming(None);
             }
@@ -196,6 +356,15 @@ impl App {
         }
     }
 
+    fn set_paused(&self, is_paused: bool) {
+        if self.audio.is_paused() == is_paused {
+            return;
+        }
+
+        self.audio.set_paused(is_paused);
+        self.discord.set_paused(is_paused);
+    }
+
     fn set_state(&self, state: AppState) {
         *self.state.lock() = state;
     }
@@ -204,8 +373,14 @@ impl App {
         self.state.lock().clone()
     }
 
-    fn set_config(&self, config: Config) {
+    fn set_config(&self, mut config: Config) {
         let mut previous_config = self.config.lock();
+
+        if let Some(previous_config) = previous_config.as_ref() {
+            config.active_profile = previous_config.active_profile.clone();
+        }
+
+        self.audio.set_config(config.effective_audio());
         *previous_config = Some(config);
     }
 
@@ -223,6 +398,8 @@ impl App {
         if let Some(config) = config.as_mut() {
             cb(config);
             config.save();
+
+            self.audio.set_config(config.effective_audio());
         }
     }
 
@@ -278,8 +455,60 @@ impl AppContext {
         self.audio.selected_source()
     }
 
-    pub fn meter_value_ranged(&self) -> (f32, f32) {
-        self.audio.meter_value_ranged()
+    pub fn source_peak(&self, source: &Source) -> f32 {
+        self.audio.source_peak(source)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.audio.is_paused()
+    }
+
+    pub fn agc_stats(&self) -> AgcStats {
+        self.audio.agc_stats()
+    }
+
+    pub fn meter_dbfs(&self) -> (f32, f32) {
+        self.audio.meter_dbfs()
+    }
+
+    pub fn peak_hold_db(&self) -> (f32, f32) {
+        self.audio.peak_hold_db()
+    }
+
+    pub fn clipped(&self) -> (bool, bool) {
+        self.audio.clipped()
+    }
+
+    pub fn correlation(&self) -> f32 {
+        self.audio.correlation()
+    }
+
+    pub fn balance(&self) -> f32 {
+        self.audio.balance()
+    }
+
+    pub fn loudness(&self) -> Loudness {
+        self.audio.loudness()
+    }
+
+    pub fn spectrum(&self) -> Vec<Band> {
+        self.audio.spectrum()
+    }
+
+    pub fn waveform(&self) -> Vec<Envelope> {
+        self.audio.waveform()
+    }
+
+    pub fn recent_frames(&self) -> Vec<[Sample; 2]> {
+        self.audio.recent_frames()
+    }
+
+    pub fn loudness_history(&self) -> Vec<HistoryPoint> {
+        self.audio.loudness_history()
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        self.audio.buffer_stats()
     }
 
     pub fn discord_state(&self) -> dickcord::State {
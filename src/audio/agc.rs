@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use crate::state::AgcConfig;
+
+use super::{kernels, Sample, SAMPLE_RATE};
+
+/// Anything at or above this is clipping once it is converted for the encoder
+const CLIP_LEVEL: f32 = 1.;
+
+/// How long it takes for the long-term level to fall by about 8.7 dB
+const LEVEL_DECAY: Duration = Duration::from_secs(3);
+
+/// Quieter than this is treated as silence, which shouldn't be boosted
+const SILENCE_DB: f32 = -50.;
+
+/// How fast the gain is raised and lowered, in dB per second
+const RAISE_RATE_DB: f32 = 1.;
+const LOWER_RATE_DB: f32 = 6.;
+
+/// The most the gain can be lowered, in dB
+const MAX_CUT_DB: f32 = 24.;
+
+/// Slowly adjusts the input gain to keep the peaks of the processed signal in a safe window
+pub struct Agc {
+    /// The gain that is being worked towards, in dB
+    gain_db: f32,
+    /// The gain that was applied at the end of the last block
+    applied_gain: f32,
+
+    /// Peak of the processed signal, falling off slowly
+    level: f32,
+    is_clipping: bool,
+
+    stats: AgcStats,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AgcStats {
+    pub gain_db: f32,
+    /// Amount of times the processed signal started clipping
+    pub clips: u64,
+    pub last_clip: Option<Instant>,
+}
+
+impl Agc {
+    pub fn new() -> Self {
+        Self {
+            gain_db: 0.,
+            applied_gain: 1.,
+            level: 0.,
+            is_clipping: false,
+            stats: Default::default(),
+        }
+    }
+
+    /// Applies the input gain, ramping from the previous one to avoid zipper noise
+    pub fn apply(&mut self, samples: &mut [Sample], config: &AgcConfig) {
+        let target = if config.enabled {
+            10f32.powf(self.gain_db / 20.)
+        } else {
+            1.
+        };
+
+        if self.applied_gain == 1. && target == 1. {
+            return;
+        }
+
+        let frames = (samples.len() / 2).max(1);
+        let step = (target - self.applied_gain) / frames as f32;
+
+        for frame in samples.chunks_exact_mut(2) {
+            self.applied_gain += step;
+
+            frame[0] *= self.applied_gain;
+            frame[1] *= self.applied_gain;
+        }
+
+        self.applied_gain = target;
+    }
+
+    /// Adjusts the gain for the next block based on the processed signal
+    pub fn observe(&mut self, samples: &[Sample], config: &AgcConfig) {
+        if !config.enabled {
+            self.gain_db = 0.;
+            self.level = 0.;
+            self.stats.gain_db = 0.;
+            return;
+        }
+
+        let seconds = (samples.len() / 2) as f32 / SAMPLE_RATE as f32;
+        let peak = kernels::peak(samples);
+
+        self.level = peak.max(self.level * (-seconds / LEVEL_DECAY.as_secs_f32()).exp());
+        let level_db = 20. * self.level.max(f32::EPSILON).log10();
+
+        let is_clipping = peak >= CLIP_LEVEL;
+
+        if is_clipping {
+            if !self.is_clipping {
+                self.stats.clips += 1;
+            }
+
+            self.stats.last_clip = Some(Instant::now());
+
+            // Clipping can't wait, so get the peak back into the window right away
+            self.gain_db -= 20. * peak.log10() - config.max_peak_db;
+            self.level = 10f32.powf(config.max_peak_db / 20.);
+        } else if level_db > config.max_peak_db {
+            self.gain_db -= LOWER_RATE_DB * seconds;
+        } else if level_db < config.min_peak_db && level_db > SILENCE_DB {
+            self.gain_db += RAISE_RATE_DB * seconds;
+        }
+
+        self.is_clipping = is_clipping;
+        self.gain_db = self.gain_db.clamp(-MAX_CUT_DB, config.max_boost_db.max(0.));
+        self.stats.gain_db = self.gain_db;
+    }
+
+    pub fn stats(&self) -> AgcStats {
+        self.stats
+    }
+}
+
+impl Default for Agc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,152 +1,527 @@
-use std::{sync::Arc, thread, time::Duration};
-
-use crossbeam::atomic::AtomicCell;
-use multiversion::multiversion;
+use std::{
+    f32::consts::SQRT_2,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam::{
+    atomic::AtomicCell,
+    channel::{bounded, Receiver, Sender},
+};
 use parking_lot::Mutex;
-
-use crate::interface::TARGET_FPS;
-
-use super::{Sample, SAMPLE_IN_BYTES, SAMPLE_RATE};
+use ringbuf::{HeapConsumer, HeapProducer, HeapRb};
+
+use crate::{interface::TARGET_FPS, state::MeterBallistics};
+
+use super::{
+    history::{History, HistoryPoint},
+    kernels,
+    loudness::{Loudness, LoudnessMeter},
+    scope::{Envelope, Scope},
+    spectrum::{Band, Spectrum},
+    true_peak::TruePeak,
+    Sample, SAMPLE_IN_BYTES, SAMPLE_RATE,
+};
+
+/// How many ticks without audio pass before silence is analyzed in its place
+const STALL_TICKS: u32 = TARGET_FPS / 10;
+/// How many ticks without audio pass before the analysis thread goes to sleep.
+/// Long enough for the slowest meters to fall back to silence.
+const IDLE_TICKS: u32 = TARGET_FPS * 10;
 
 /// Measures dBFS of a single channel
 pub struct Meter {
-    window_size: AtomicCell<usize>,
+    current_value: f32,
+
+    window: Window,
+
+    true_peak: TruePeak,
+    /// The highest recent true peak, falling off after a while
+    peak_hold: f32,
+    /// Ticks since the held peak was last raised
+    peak_hold_age: u32,
+    /// Set when the true peak reaches 0 dBTP, until it is reset
+    clipped: bool,
+    /// Whether the true peak reached 0 dBTP in the last tick
+    is_clipping: bool,
+    /// The highest true peak since the meter was created
+    max_true_peak: f32,
+}
+
+/// Holds a fixed amount of the most recent samples
+pub(super) struct Window {
+    pub samples: Vec<Sample>,
+    /// Where the next sample goes, which is also where the oldest one is
+    pub position: usize,
+}
+
+/// The latest measurements of the outgoing stream, as published by the analysis thread
+pub struct Analysis {
+    meter: AtomicCell<MeterReadout>,
+    loudness: AtomicCell<Loudness>,
+    spectrum: Mutex<Vec<Band>>,
+    waveform: Mutex<Vec<Envelope>>,
+    frames: Mutex<Vec<[Sample; 2]>>,
+    history: Mutex<Vec<HistoryPoint>>,
+    session: AtomicCell<SessionMeasurements>,
+
+    ballistics: AtomicCell<MeterBallistics>,
+    reset_clips: AtomicCell<bool>,
+    reset_loudness: AtomicCell<bool>,
+
+    /// Wakes the analysis thread up when it is idle
+    wake: Sender<()>,
+}
+
+/// The audio thread's end of the analysis, which never blocks
+pub struct AnalysisInput {
+    producer: HeapProducer<Sample>,
+    wake: Sender<()>,
+}
+
+/// Everything that is measured, only touched by the analysis thread
+struct Analyzers {
+    meter: StereoMeter,
+    spectrum: Spectrum,
+    scope: Scope,
+    loudness: LoudnessMeter,
+    history: History,
 
-    current_value: AtomicCell<f32>,
+    /// Ticks processed so far, used to do expensive work less often
+    ticks: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MeterReadout {
+    /// The level of each channel, in dBFS
+    pub dbfs: (f32, f32),
+    /// The held true peak of each channel, in dBTP
+    pub peak_hold_db: (f32, f32),
+    /// Whether each channel has clipped since the clip indicators were last reset
+    pub clipped: (bool, bool),
+    /// How alike the channels are, from -1 when they cancel out to 1 when they are the same
+    pub correlation: f32,
+    /// How much louder the right channel is than the left one, in dB
+    pub balance: f32,
+}
 
-    buffer: Mutex<Vec<f32>>,
+/// Measurements over the whole session, which resets from the interface leave alone
+#[derive(Debug, Clone, Copy)]
+pub struct SessionMeasurements {
+    /// In LUFS
+    pub integrated_loudness: f32,
+    /// In dBTP
+    pub max_true_peak: f32,
+    /// How many times the stream started clipping
+    pub clips: u64,
 }
 
 impl Meter {
     const DEFAULT_WINDOW_SIZE: usize = SAMPLE_RATE / 24;
-    const DB_RANGE: f32 = 100.;
 
-    /// The smoothing modifiers. Higher values equals less smoothing.
-    const MAX_SMOOTHING: f32 = 0.1 / 2.;
-    const MIN_SMOOTHING: f32 = 0.2;
+    /// Time constant of a VU meter, which reaches 99% of a step in 300 ms
+    const VU_TIME_CONSTANT: f32 = 0.065;
+    /// Time constant of a PPM, which follows its 10 ms integration time
+    const PPM_TIME_CONSTANT: f32 = 0.01;
 
-    /// The minimum smoothing boundary
-    const SMOOTHING_BOUNDARY: f32 = 0.5;
+    /// How fast the meter falls, in dB per second. 24 dB in 2.8 s for a PPM, 20 dB in 1.7 s for digital peak.
+    const PPM_FALL_DB: f32 = 24. / 2.8;
+    const DIGITAL_FALL_DB: f32 = 20. / 1.7;
+
+    /// How long the peak is held before it falls, in ticks
+    const PEAK_HOLD_TICKS: u32 = TARGET_FPS * 3 / 2;
+    /// How fast the held peak falls, in dB per second
+    const PEAK_DECAY_DB: f32 = 20.;
 
     pub fn new() -> Self {
         Self {
-            window_size: Self::DEFAULT_WINDOW_SIZE.into(),
             current_value: Default::default(),
-            buffer: Default::default(),
+            window: Window::new(Self::DEFAULT_WINDOW_SIZE),
+            true_peak: Default::default(),
+            peak_hold: Default::default(),
+            peak_hold_age: Default::default(),
+            clipped: Default::default(),
+            is_clipping: Default::default(),
+            max_true_peak: Default::default(),
         }
     }
 
-    pub fn write(&self, buf: &[u8]) {
-        let mut buffer = self.buffer.lock();
-        let window_size = self.window_size.load();
+    pub fn process(&mut self, ballistics: MeterBallistics) {
+        let tick = 1. / TARGET_FPS as f32;
+        let current_value = self.current_value;
+        let window = &self.window;
+
+        let value = match ballistics {
+            MeterBallistics::Vu => {
+                let mean_square =
+                    window.samples.iter().map(|s| s * s).sum::<f32>() / window.samples.len() as f32;
+
+                // Calibrated to a sine, so it reads the same as the peak meters for one
+                let level = mean_square.sqrt() * SQRT_2;
+                let coefficient = (-tick / Self::VU_TIME_CONSTANT).exp();
+
+                level + (current_value - level) * coefficient
+            }
+            MeterBallistics::Ppm => {
+                let peak = kernels::peak(&window.samples);
+
+                if peak > current_value {
+                    let coefficient = (-tick / Self::PPM_TIME_CONSTANT).exp();
+                    peak + (current_value - peak) * coefficient
+                } else {
+                    fall(current_value, Self::PPM_FALL_DB * tick).max(peak)
+                }
+            }
+            MeterBallistics::DigitalPeak => {
+                let peak = kernels::peak(&window.samples);
+                peak.max(fall(current_value, Self::DIGITAL_FALL_DB * tick))
+            }
+        };
+
+        self.current_value = value;
+        self.process_true_peak();
+    }
 
-        let samples = raw_samples_from_bytes(buf);
+    fn process_true_peak(&mut self) {
+        let true_peak = self.true_peak.take();
+
+        self.is_clipping = true_peak >= 1.;
+        self.clipped |= self.is_clipping;
+        self.max_true_peak = self.max_true_peak.max(true_peak);
+
+        if true_peak >= self.peak_hold {
+            self.peak_hold = true_peak;
+            self.peak_hold_age = 0;
+        } else if self.peak_hold_age < Self::PEAK_HOLD_TICKS {
+            self.peak_hold_age += 1;
+        } else {
+            let decay = Self::PEAK_DECAY_DB / TARGET_FPS as f32;
+            self.peak_hold = fall(self.peak_hold, decay);
+        }
+    }
 
-        buffer.extend_from_slice(&samples);
+    /// The held true peak, in dBTP
+    pub fn peak_hold_db(&self) -> f32 {
+        self.peak_hold.log10() * 20.
+    }
+
+    pub fn value(&self) -> f32 {
+        self.current_value
+    }
 
-        let overflow = buffer.len() as isize + -(window_size as isize);
-        let overflow = overflow.max(0) as usize;
+    pub fn dbfs(&self) -> f32 {
+        self.value().log10() * 20.
+    }
+}
 
-        if overflow > 0 {
-            buffer.drain(..overflow);
+pub struct StereoMeter {
+    left: Meter,
+    right: Meter,
+
+    /// How alike the channels are, from -1 when they cancel out to 1 when they are the same
+    correlation: f32,
+    /// How much louder the right channel is than the left one, in dB
+    balance: f32,
+
+    /// How many times either channel started clipping
+    clips: u64,
+    was_clipping: bool,
+}
+
+impl StereoMeter {
+    /// How quickly the correlation and balance follow the signal, in seconds
+    const CORRELATION_TIME_CONSTANT: f32 = 0.3;
+    /// Below about -60 dBFS over the window, which is treated as silence
+    const SILENCE_ENERGY: f32 = 1e-6 * Meter::DEFAULT_WINDOW_SIZE as f32;
+    const MAX_BALANCE_DB: f32 = 60.;
+
+    pub fn new() -> Self {
+        Self {
+            left: Meter::new(),
+            right: Meter::new(),
+            correlation: Default::default(),
+            balance: Default::default(),
+            clips: 0,
+            was_clipping: false,
         }
     }
 
-    pub fn drain(&self, amount: usize) {
-        let mut buffer = self.buffer.lock();
+    pub fn write(&mut self, samples: &[Sample]) {
+        let Self { left, right, .. } = self;
+
+        // Both windows are always written together, so they share the same position
+        let mut frames = samples;
+
+        while !frames.is_empty() {
+            let start = left.window.position;
+            let length = (left.window.samples.len() - start).min(frames.len() / 2);
+
+            if length == 0 {
+                break;
+            }
 
-        buffer.extend(vec![0.; amount]);
-        buffer.drain(..amount);
+            let left_samples = &mut left.window.samples[start..start + length];
+            let right_samples = &mut right.window.samples[start..start + length];
+
+            kernels::deinterleave(&frames[..length * 2], left_samples, right_samples);
+
+            left.true_peak.write(left_samples);
+            right.true_peak.write(right_samples);
+
+            left.window.advance(length);
+            right.window.advance(length);
+            frames = &frames[length * 2..];
+        }
     }
 
-    pub fn process(&self) {
-        process_multiversioned(self)
+    pub fn process(&mut self, ballistics: MeterBallistics) {
+        self.left.process(ballistics);
+        self.right.process(ballistics);
+        self.process_correlation();
+
+        let is_clipping = self.left.is_clipping || self.right.is_clipping;
+
+        if is_clipping && !self.was_clipping {
+            self.clips += 1;
+        }
+
+        self.was_clipping = is_clipping;
     }
 
-    pub fn value(&self) -> f32 {
-        self.current_value.load()
+    fn process_correlation(&mut self) {
+        let (left_energy, right_energy, product) = self
+            .left
+            .window
+            .samples
+            .iter()
+            .zip(&self.right.window.samples)
+            .fold(
+                (0., 0., 0.),
+                |(left_energy, right_energy, product), (l, r)| {
+                    (left_energy + l * l, right_energy + r * r, product + l * r)
+                },
+            );
+
+        // Silence has no correlation or balance, so let them settle back in the middle
+        let (correlation, balance) = if left_energy + right_energy < Self::SILENCE_ENERGY {
+            (0., 0.)
+        } else {
+            let correlation = product / (left_energy * right_energy).sqrt().max(f32::EPSILON);
+            let balance =
+                10. * (right_energy.max(f32::EPSILON) / left_energy.max(f32::EPSILON)).log10();
+
+            (
+                correlation.clamp(-1., 1.),
+                balance.clamp(-Self::MAX_BALANCE_DB, Self::MAX_BALANCE_DB),
+            )
+        };
+
+        let coefficient = (-1. / (TARGET_FPS as f32 * Self::CORRELATION_TIME_CONSTANT)).exp();
+        let smooth = |previous: f32, next: f32| next + (previous - next) * coefficient;
+
+        self.correlation = smooth(self.correlation, correlation);
+        self.balance = smooth(self.balance, balance);
     }
 
-    pub fn dbfs(&self) -> f32 {
-        self.value().log10() * 20.
+    pub fn readout(&self) -> MeterReadout {
+        MeterReadout {
+            dbfs: (self.left.dbfs(), self.right.dbfs()),
+            peak_hold_db: (self.left.peak_hold_db(), self.right.peak_hold_db()),
+            clipped: (self.left.clipped, self.right.clipped),
+            correlation: self.correlation,
+            balance: self.balance,
+        }
     }
 
-    pub fn value_ranged(&self) -> f32 {
-        let ranged = (Self::DB_RANGE + self.dbfs()) / Self::DB_RANGE;
-        ranged.max(0.).powf(2.)
+    /// The highest true peak of either channel since the meter was created, in dBTP
+    pub fn max_true_peak_db(&self) -> f32 {
+        self.left
+            .max_true_peak
+            .max(self.right.max_true_peak)
+            .log10()
+            * 20.
+    }
+
+    pub fn reset_clips(&mut self) {
+        self.left.clipped = false;
+        self.right.clipped = false;
     }
 }
 
-#[multiversion(targets("x86_64+avx2"))]
-fn process_multiversioned(meter: &Meter) {
-    let buffer = meter.buffer.lock();
+impl Analysis {
+    /// How much audio can wait for the analysis thread, in samples
+    const QUEUE_SIZE: usize = SAMPLE_RATE;
+
+    /// Creates the analysis and starts its thread, returning the end the audio is written to
+    pub fn spawn() -> (Arc<Self>, AnalysisInput) {
+        let (producer, consumer) = HeapRb::new(Self::QUEUE_SIZE).split();
+        let (wake, woken) = bounded(1);
+
+        let analysis = Arc::new(Self {
+            meter: AtomicCell::new(MeterReadout::SILENT),
+            loudness: AtomicCell::new(Loudness::SILENT),
+            spectrum: Default::default(),
+            waveform: Default::default(),
+            frames: Default::default(),
+            history: Default::default(),
+            session: AtomicCell::new(SessionMeasurements::SILENT),
+            ballistics: Default::default(),
+            reset_clips: Default::default(),
+            reset_loudness: Default::default(),
+            wake: wake.clone(),
+        });
+
+        spawn_analysis_thread(analysis.clone(), consumer, woken);
+        (analysis, AnalysisInput { producer, wake })
+    }
+
+    pub fn set_ballistics(&self, ballistics: MeterBallistics) {
+        self.ballistics.store(ballistics);
+    }
+
+    pub fn reset_clips(&self) {
+        self.reset_clips.store(true);
+        self.wake.try_send(()).ok();
+    }
+
+    /// Starts measuring the integrated loudness from scratch
+    pub fn reset_loudness(&self) {
+        self.reset_loudness.store(true);
+        self.wake.try_send(()).ok();
+    }
 
-    let current_value = meter.current_value.load();
-    let max_value = buffer
-        .iter()
-        .fold(0f32, |acc, x| faster_max(acc, (*x).abs()));
+    pub fn meter(&self) -> MeterReadout {
+        self.meter.load()
+    }
 
-    let difference = (current_value - max_value).abs();
+    pub fn loudness(&self) -> Loudness {
+        self.loudness.load()
+    }
 
-    if max_value > current_value {
-        meter.current_value.store(max_value);
-    } else {
-        let max_smoothing =
-            (1. - Meter::MAX_SMOOTHING) + Meter::MAX_SMOOTHING * Meter::SMOOTHING_BOUNDARY;
+    pub fn spectrum(&self) -> Vec<Band> {
+        self.spectrum.lock().clone()
+    }
 
-        let min_smoothing =
-            (1. - Meter::MIN_SMOOTHING) + Meter::MIN_SMOOTHING * Meter::SMOOTHING_BOUNDARY;
+    /// The waveform, from oldest to newest
+    pub fn waveform(&self) -> Vec<Envelope> {
+        self.waveform.lock().clone()
+    }
 
-        let flipped_difference = (-difference) + 1.;
-        let smoothing_factor = flipped_difference * max_value.powf(0.1);
+    /// The most recent frames, from oldest to newest
+    pub fn frames(&self) -> Vec<[Sample; 2]> {
+        self.frames.lock().clone()
+    }
 
-        let smoothing = min_smoothing + (max_smoothing - min_smoothing) * smoothing_factor;
-        let result = current_value * smoothing;
+    /// The loudness of every second over the last while, from oldest to newest
+    pub fn history(&self) -> Vec<HistoryPoint> {
+        self.history.lock().clone()
+    }
 
-        meter.current_value.store(result);
+    pub fn session(&self) -> SessionMeasurements {
+        self.session.load()
     }
 }
 
-pub struct StereoMeter {
-    left: Meter,
-    right: Meter,
+impl AnalysisInput {
+    /// Queues interleaved stereo samples for analysis, dropping what doesn't fit
+    pub fn write(&mut self, samples: &[Sample]) {
+        // Only whole frames, so the channels never get swapped
+        let length = samples.len().min(self.producer.free_len() / 2 * 2);
+
+        self.producer.push_slice(&samples[..length]);
+        self.wake.try_send(()).ok();
+    }
 }
 
-impl StereoMeter {
-    pub fn new() -> Self {
+impl Analyzers {
+    fn new() -> Self {
         Self {
-            left: Meter::new(),
-            right: Meter::new(),
+            meter: StereoMeter::new(),
+            spectrum: Spectrum::new(),
+            scope: Scope::new(),
+            loudness: LoudnessMeter::new(),
+            history: History::new(),
+            ticks: 0,
         }
     }
 
-    pub fn write(&self, buf: &[u8]) {
-        let samples = buf.chunks_exact(SAMPLE_IN_BYTES * 2);
+    fn write(&mut self, samples: &[Sample]) {
+        self.meter.write(samples);
+        self.spectrum.write(samples);
+        self.scope.write(samples);
+        self.loudness.write(samples);
+        self.history
+            .write(samples, self.loudness.loudness().short_term);
+    }
+
+    /// Applies requests from the interface, updates everything and publishes the results
+    fn process(&mut self, analysis: &Analysis) {
+        if analysis.reset_clips.swap(false) {
+            self.meter.reset_clips();
+        }
+
+        if analysis.reset_loudness.swap(false) {
+            self.loudness.reset();
+        }
+
+        self.meter.process(analysis.ballistics.load());
+        self.spectrum.process();
 
-        let mut left = vec![];
-        let mut right = vec![];
+        analysis.meter.store(self.meter.readout());
+        analysis.loudness.store(self.loudness.loudness());
 
-        for chunk in samples {
-            left.extend_from_slice(&chunk[..SAMPLE_IN_BYTES]);
-            right.extend_from_slice(&chunk[SAMPLE_IN_BYTES..]);
+        self.spectrum.publish(&mut analysis.spectrum.lock());
+        self.scope
+            .publish(&mut analysis.waveform.lock(), &mut analysis.frames.lock());
+        self.history.publish(&mut analysis.history.lock());
+
+        // Integrating the whole session takes a while, so it is only done every second
+        if self.ticks.is_multiple_of(TARGET_FPS) {
+            analysis.session.store(SessionMeasurements {
+                integrated_loudness: self.loudness.session_integrated(),
+                max_true_peak: self.meter.max_true_peak_db(),
+                clips: self.meter.clips,
+            });
         }
 
-        self.left.write(&left);
-        self.right.write(&right);
+        self.ticks = self.ticks.wrapping_add(1);
     }
+}
+
+impl MeterReadout {
+    const SILENT: Self = Self {
+        dbfs: (f32::NEG_INFINITY, f32::NEG_INFINITY),
+        peak_hold_db: (f32::NEG_INFINITY, f32::NEG_INFINITY),
+        clipped: (false, false),
+        correlation: 0.,
+        balance: 0.,
+    };
+}
 
-    pub fn process(&self) {
-        self.left.process();
-        self.right.process();
+impl SessionMeasurements {
+    const SILENT: Self = Self {
+        integrated_loudness: f32::NEG_INFINITY,
+        max_true_peak: f32::NEG_INFINITY,
+        clips: 0,
+    };
+}
+
+impl Window {
+    pub fn new(size: usize) -> Self {
+        Self {
+            samples: vec![0.; size],
+            position: 0,
+        }
     }
 
-    pub fn drain(&self, amount: usize) {
-        self.left.drain(amount);
-        self.right.drain(amount);
+    pub fn push(&mut self, sample: Sample) {
+        self.samples[self.position] = sample;
+        self.advance(1);
     }
 
-    pub fn value_ranged(&self) -> (f32, f32) {
-        (self.left.value_ranged(), self.right.value_ranged())
+    fn advance(&mut self, amount: usize) {
+        self.position = (self.position + amount) % self.samples.len();
     }
 }
 
@@ -156,36 +531,79 @@ impl Default for StereoMeter {
     }
 }
 
-fn faster_max(a: f32, b: f32) -> f32 {
-    if a > b {
-        a
-    } else {
-        b
-    }
+/// Converts bytes into [Sample]s, replacing the contents of `samples`.
+pub fn read_samples(bytes: &[u8], samples: &mut Vec<Sample>) {
+    samples.resize(bytes.len() / SAMPLE_IN_BYTES, 0.);
+    kernels::bytes_to_samples(bytes, samples);
 }
 
-/// Converts a slice of bytes into a vec of [Sample].
-pub fn raw_samples_from_bytes(bytes: &[u8]) -> Vec<Sample> {
-    bytes
-        .chunks_exact(SAMPLE_IN_BYTES)
-        .map(|b| {
-            let arr: [u8; SAMPLE_IN_BYTES] = [b[0], b[1], b[2], b[3]];
-            Sample::from_le_bytes(arr)
-        })
-        .collect()
+/// Converts [Sample]s into bytes, replacing the contents of `bytes`.
+pub fn write_samples(samples: &[Sample], bytes: &mut Vec<u8>) {
+    bytes.resize(samples.len() * SAMPLE_IN_BYTES, 0);
+    kernels::samples_to_bytes(samples, bytes);
 }
 
-pub fn spawn_analysis_thread(meter: Arc<StereoMeter>) {
+/// Lowers a level by the given amount of dB
+fn fall(value: f32, db: f32) -> f32 {
+    value * 10f32.powf(-db / 20.)
+}
+
+pub fn sample_from_bytes(bytes: &[u8]) -> Sample {
+    let arr: [u8; SAMPLE_IN_BYTES] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    Sample::from_le_bytes(arr)
+}
+
+/// Analyzes the queued audio at the interface's frame rate, stopping once nothing has been streamed for a while
+fn spawn_analysis_thread(
+    analysis: Arc<Analysis>,
+    mut consumer: HeapConsumer<Sample>,
+    woken: Receiver<()>,
+) {
     let run = move || {
-        let tick_rate = 1. / TARGET_FPS as f32;
-        let samples_to_drain = (SAMPLE_RATE as f32 * tick_rate) as usize;
+        let tick_rate = Duration::from_secs_f32(1. / TARGET_FPS as f32);
+        let frames_per_tick = SAMPLE_RATE / TARGET_FPS as usize;
+
+        let mut analyzers = Analyzers::new();
+        let mut samples = vec![0.; Analysis::QUEUE_SIZE];
+        let silence = vec![0.; frames_per_tick * 2];
+
+        let mut idle_ticks = 0;
 
         loop {
-            let meter = meter.clone();
-            meter.process();
-            meter.drain(samples_to_drain);
+            let length = consumer.pop_slice(&mut samples);
+
+            if length > 0 {
+                idle_ticks = 0;
+                analyzers.write(&samples[..length]);
+            } else {
+                idle_ticks += 1;
+
+                // Audio comes in chunks, so only fill in silence once it is clearly not coming
+                if idle_ticks > STALL_TICKS {
+                    analyzers.write(&silence);
+                }
+            }
+
+            analyzers.process(&analysis);
+
+            if idle_ticks >= IDLE_TICKS {
+                // Forget wakeups from before, then sleep until there is audio or a request
+                while woken.try_recv().is_ok() {}
+
+                let sleeping_since = Instant::now();
+
+                if consumer.is_empty() && woken.recv().is_err() {
+                    break;
+                }
+
+                analyzers.history.skip(sleeping_since.elapsed());
+
+                // Just enough to handle what woke it up, before going back to sleep if it was a request
+                idle_ticks = IDLE_TICKS - 1;
+                continue;
+            }
 
-            thread::sleep(Duration::from_secs_f32(tick_rate));
+            thread::sleep(tick_rate);
         }
     };
 
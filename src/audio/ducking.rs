@@ -0,0 +1,61 @@
+use crate::state::DuckingConfig;
+
+use super::{Sample, SAMPLE_RATE};
+
+/// Lowers the stream while people in the voice channel are talking
+pub struct Ducker {
+    gain: f32,
+}
+
+impl Ducker {
+    pub fn new() -> Self {
+        Self { gain: 1. }
+    }
+
+    pub fn apply(&mut self, samples: &mut [Sample], is_ducked: bool, config: &DuckingConfig) {
+        let target = if is_ducked && config.enabled {
+            10f32.powf(-config.amount_db.abs() / 20.)
+        } else {
+            1.
+        };
+
+        if self.gain == 1. && target == 1. {
+            return;
+        }
+
+        let time = if target < self.gain {
+            config.attack_ms
+        } else {
+            config.release_ms
+        };
+
+        let coefficient = smoothing_coefficient(time as f32 / 1000.);
+
+        for frame in samples.chunks_exact_mut(2) {
+            self.gain = target + (self.gain - target) * coefficient;
+
+            frame[0] *= self.gain;
+            frame[1] *= self.gain;
+        }
+
+        // Settle once the difference is inaudible, so the fast path above can kick in
+        if (self.gain - target).abs() < 0.001 {
+            self.gain = target;
+        }
+    }
+}
+
+impl Default for Ducker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the per-frame coefficient of a one-pole smoother with the given time constant
+fn smoothing_coefficient(seconds: f32) -> f32 {
+    if seconds <= 0. {
+        return 0.;
+    }
+
+    (-1. / (seconds * SAMPLE_RATE as f32)).exp()
+}
@@ -0,0 +1,117 @@
+use std::f32::consts::PI;
+
+use crate::audio::{Sample, SAMPLE_RATE};
+
+use super::{Effect, EffectContext};
+
+/// Frequencies at the edges of the range turn the filters off
+pub const MIN_FREQUENCY: f32 = 20.;
+pub const MAX_FREQUENCY: f32 = 20000.;
+
+const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Cuts frequencies below and above a range
+#[derive(Default)]
+pub struct Filter {
+    low_cut: Option<[Biquad; 2]>,
+    high_cut: Option<[Biquad; 2]>,
+}
+
+impl Effect for Filter {
+    fn process(&mut self, samples: &mut [Sample], _: &EffectContext) {
+        for filters in [&mut self.low_cut, &mut self.high_cut]
+            .into_iter()
+            .flatten()
+        {
+            for frame in samples.chunks_exact_mut(2) {
+                frame[0] = filters[0].process(frame[0]);
+                frame[1] = filters[1].process(frame[1]);
+            }
+        }
+    }
+
+    fn set_parameters(&mut self, values: &[f32]) {
+        let (low_cut, high_cut) = (values[0], values[1]);
+
+        self.low_cut = (low_cut > MIN_FREQUENCY).then(|| {
+            let filter = Biquad::highpass(low_cut, BUTTERWORTH_Q);
+            [filter.clone(), filter]
+        });
+
+        self.high_cut = (high_cut < MAX_FREQUENCY).then(|| {
+            let filter = Biquad::lowpass(high_cut, BUTTERWORTH_Q);
+            [filter.clone(), filter]
+        });
+    }
+}
+
+/// A second order filter for a single channel, using the RBJ cookbook formulas
+#[derive(Debug, Clone, Default)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    pub fn highpass(frequency: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::prepare(frequency, q);
+
+        Self::normalized(
+            (1. + cos) / 2.,
+            -(1. + cos),
+            (1. + cos) / 2.,
+            1. + alpha,
+            -2. * cos,
+            1. - alpha,
+        )
+    }
+
+    pub fn lowpass(frequency: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::prepare(frequency, q);
+
+        Self::normalized(
+            (1. - cos) / 2.,
+            1. - cos,
+            (1. - cos) / 2.,
+            1. + alpha,
+            -2. * cos,
+            1. - alpha,
+        )
+    }
+
+    /// Creates a filter from coefficients that are already normalized by a0
+    pub fn new(b0: f32, b1: f32, b2: f32, a1: f32, a2: f32) -> Self {
+        Self::normalized(b0, b1, b2, 1., a1, a2)
+    }
+
+    pub fn process(&mut self, input: Sample) -> Sample {
+        let output = self.b0 * input + self.z1;
+
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+
+        output
+    }
+
+    fn prepare(frequency: f32, q: f32) -> (f32, f32) {
+        let omega = 2. * PI * frequency / SAMPLE_RATE as f32;
+        (omega.cos(), omega.sin() / (2. * q))
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            ..Default::default()
+        }
+    }
+}
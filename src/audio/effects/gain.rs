@@ -0,0 +1,24 @@
+use crate::audio::{kernels, Sample};
+
+use super::{db_to_gain, Effect, EffectContext};
+
+/// Raises or lowers the volume by a fixed amount
+pub struct Gain {
+    gain: f32,
+}
+
+impl Effect for Gain {
+    fn process(&mut self, samples: &mut [Sample], _: &EffectContext) {
+        kernels::apply_gain(samples, self.gain);
+    }
+
+    fn set_parameters(&mut self, values: &[f32]) {
+        self.gain = db_to_gain(values[0]);
+    }
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self { gain: 1. }
+    }
+}
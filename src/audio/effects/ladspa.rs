@@ -0,0 +1,428 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::{c_char, c_int, c_ulong, c_void, CStr, CString},
+    path::{Path, PathBuf},
+};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::audio::{kernels, Sample, SAMPLE_RATE};
+
+use super::{Effect, EffectContext, ParameterInfo};
+
+/// Searched for plugins that aren't given by an absolute path, when LADSPA_PATH isn't set
+const DEFAULT_SEARCH_PATH: &str = "/usr/lib/ladspa:/usr/local/lib/ladspa:/usr/lib64/ladspa";
+
+/// How many frames are processed per run
+const MAX_FRAMES: usize = 1024;
+
+const PORT_INPUT: c_int = 0x1;
+const PORT_CONTROL: c_int = 0x4;
+const PORT_AUDIO: c_int = 0x8;
+
+const HINT_BOUNDED_BELOW: c_int = 0x1;
+const HINT_BOUNDED_ABOVE: c_int = 0x2;
+const HINT_TOGGLED: c_int = 0x4;
+const HINT_SAMPLE_RATE: c_int = 0x8;
+const HINT_LOGARITHMIC: c_int = 0x10;
+const HINT_INTEGER: c_int = 0x20;
+const HINT_DEFAULT_MASK: c_int = 0x3C0;
+const HINT_DEFAULT_MINIMUM: c_int = 0x40;
+const HINT_DEFAULT_LOW: c_int = 0x80;
+const HINT_DEFAULT_MIDDLE: c_int = 0xC0;
+const HINT_DEFAULT_HIGH: c_int = 0x100;
+const HINT_DEFAULT_MAXIMUM: c_int = 0x140;
+const HINT_DEFAULT_0: c_int = 0x200;
+const HINT_DEFAULT_1: c_int = 0x240;
+const HINT_DEFAULT_100: c_int = 0x280;
+const HINT_DEFAULT_440: c_int = 0x2C0;
+
+type Handle = *mut c_void;
+type DescriptorFunction = unsafe extern "C" fn(c_ulong) -> *const Descriptor;
+
+/// Mirrors `LADSPA_Descriptor` from ladspa.h
+#[repr(C)]
+struct Descriptor {
+    unique_id: c_ulong,
+    label: *const c_char,
+    properties: c_int,
+    name: *const c_char,
+    maker: *const c_char,
+    copyright: *const c_char,
+    port_count: c_ulong,
+    port_descriptors: *const c_int,
+    port_names: *const *const c_char,
+    port_range_hints: *const PortRangeHint,
+    implementation_data: *mut c_void,
+    instantiate: unsafe extern "C" fn(*const Descriptor, c_ulong) -> Handle,
+    connect_port: unsafe extern "C" fn(Handle, c_ulong, *mut f32),
+    activate: Option<unsafe extern "C" fn(Handle)>,
+    run: unsafe extern "C" fn(Handle, c_ulong),
+    run_adding: Option<unsafe extern "C" fn(Handle, c_ulong)>,
+    set_run_adding_gain: Option<unsafe extern "C" fn(Handle, f32)>,
+    deactivate: Option<unsafe extern "C" fn(Handle)>,
+    cleanup: unsafe extern "C" fn(Handle),
+}
+
+#[repr(C)]
+struct PortRangeHint {
+    hint_descriptor: c_int,
+    lower_bound: f32,
+    upper_bound: f32,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum LadspaError {
+    #[error("Could not find plugin library {0}")]
+    NotFound(String),
+    #[error("Could not load plugin library: {0}")]
+    Load(String),
+    #[error("{0} is not a LADSPA plugin library")]
+    NotLadspa(String),
+    #[error("No plugin labeled {0} in the library")]
+    NoSuchLabel(String),
+    #[error("Only mono and stereo plugins are supported")]
+    UnsupportedChannels,
+    #[error("The plugin failed to instantiate")]
+    Instantiate,
+}
+
+/// A plugin in a loaded library.
+/// Libraries are never unloaded, so the descriptor lives as long as the program.
+#[derive(Clone, Copy)]
+pub struct Plugin {
+    descriptor: &'static Descriptor,
+}
+
+// Descriptors are immutable once loaded
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+lazy_static! {
+    /// Plugins that have been looked up, including failures, so rendering doesn't keep reloading them
+    static ref PLUGINS: Mutex<HashMap<(String, String), Result<Plugin, LadspaError>>> =
+        Default::default();
+}
+
+impl Plugin {
+    /// Finds the plugin with the label in the library at the path, loading the library if needed
+    pub fn load(path: &str, label: &str) -> Result<Self, LadspaError> {
+        PLUGINS
+            .lock()
+            .entry((path.to_owned(), label.to_owned()))
+            .or_insert_with(|| Self::find(path, label))
+            .clone()
+    }
+
+    fn find(path: &str, label: &str) -> Result<Self, LadspaError> {
+        let resolved = resolve_path(path).ok_or_else(|| LadspaError::NotFound(path.to_owned()))?;
+        let c_path = CString::new(resolved.to_string_lossy().as_bytes())
+            .map_err(|_| LadspaError::NotFound(path.to_owned()))?;
+
+        let library = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW) };
+
+        if library.is_null() {
+            let error = unsafe { CStr::from_ptr(libc::dlerror()) };
+            return Err(LadspaError::Load(error.to_string_lossy().into_owned()));
+        }
+
+        let symbol = unsafe { libc::dlsym(library, c"ladspa_descriptor".as_ptr()) };
+
+        if symbol.is_null() {
+            return Err(LadspaError::NotLadspa(path.to_owned()));
+        }
+
+        let descriptor_function: DescriptorFunction = unsafe { std::mem::transmute(symbol) };
+
+        for index in 0.. {
+            let descriptor = unsafe { descriptor_function(index) };
+
+            if descriptor.is_null() {
+                break;
+            }
+
+            let descriptor = unsafe { &*descriptor };
+            let descriptor_label = unsafe { CStr::from_ptr(descriptor.label) };
+
+            if descriptor_label.to_bytes() == label.as_bytes() {
+                return Ok(Self { descriptor });
+            }
+        }
+
+        Err(LadspaError::NoSuchLabel(label.to_owned()))
+    }
+
+    pub fn name(&self) -> String {
+        unsafe { CStr::from_ptr(self.descriptor.name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn parameters(&self) -> Vec<ParameterInfo> {
+        self.control_inputs()
+            .map(|port| self.parameter_info(port))
+            .collect()
+    }
+
+    fn ports(&self) -> impl Iterator<Item = (usize, c_int)> + '_ {
+        let count = self.descriptor.port_count as usize;
+        let descriptors =
+            unsafe { std::slice::from_raw_parts(self.descriptor.port_descriptors, count) };
+
+        descriptors.iter().copied().enumerate()
+    }
+
+    fn control_inputs(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ports()
+            .filter(|(_, d)| d & PORT_CONTROL != 0 && d & PORT_INPUT != 0)
+            .map(|(index, _)| index)
+    }
+
+    fn audio_ports(&self, is_input: bool) -> Vec<usize> {
+        self.ports()
+            .filter(|(_, d)| d & PORT_AUDIO != 0 && (d & PORT_INPUT != 0) == is_input)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn parameter_info(&self, port: usize) -> ParameterInfo {
+        let name = unsafe { CStr::from_ptr(*self.descriptor.port_names.add(port)) }
+            .to_string_lossy()
+            .into_owned();
+
+        let hint = unsafe { &*self.descriptor.port_range_hints.add(port) };
+        let hints = hint.hint_descriptor;
+
+        let scale = if hints & HINT_SAMPLE_RATE != 0 {
+            SAMPLE_RATE as f32
+        } else {
+            1.
+        };
+
+        let (min, max) = if hints & HINT_TOGGLED != 0 {
+            (0., 1.)
+        } else {
+            let min = if hints & HINT_BOUNDED_BELOW != 0 {
+                hint.lower_bound * scale
+            } else {
+                0.
+            };
+
+            let max = if hints & HINT_BOUNDED_ABOVE != 0 {
+                hint.upper_bound * scale
+            } else {
+                min + 1.
+            };
+
+            (min, max.max(min))
+        };
+
+        let is_logarithmic = hints & HINT_LOGARITHMIC != 0 && min > 0.;
+        let interpolate = |amount: f32| {
+            if is_logarithmic {
+                (min.ln() * (1. - amount) + max.ln() * amount).exp()
+            } else {
+                min * (1. - amount) + max * amount
+            }
+        };
+
+        let default = match hints & HINT_DEFAULT_MASK {
+            HINT_DEFAULT_MINIMUM => min,
+            HINT_DEFAULT_LOW => interpolate(0.25),
+            HINT_DEFAULT_MIDDLE => interpolate(0.5),
+            HINT_DEFAULT_HIGH => interpolate(0.75),
+            HINT_DEFAULT_MAXIMUM => max,
+            HINT_DEFAULT_0 => 0.,
+            HINT_DEFAULT_1 => 1.,
+            HINT_DEFAULT_100 => 100.,
+            HINT_DEFAULT_440 => 440.,
+            _ => min,
+        };
+
+        let step = if hints & (HINT_INTEGER | HINT_TOGGLED) != 0 {
+            1.
+        } else {
+            (max - min) / 100.
+        };
+
+        ParameterInfo {
+            name,
+            unit: "",
+            default: default.clamp(min, max),
+            min,
+            max,
+            step,
+        }
+    }
+}
+
+/// Runs a LADSPA plugin. Mono plugins get an instance per channel.
+pub struct LadspaEffect {
+    plugin: Plugin,
+    instances: Vec<Instance>,
+
+    /// The ports parameters are written to, in the order of [Plugin::parameters]
+    control_inputs: Box<[usize]>,
+
+    inputs: [Box<[f32]>; 2],
+    outputs: [Box<[f32]>; 2],
+}
+
+struct Instance {
+    handle: Handle,
+
+    /// A value for every port, though only control ports are connected to them
+    controls: Box<[f32]>,
+}
+
+// Instances are only ever used by the thread that owns the effect
+unsafe impl Send for LadspaEffect {}
+
+impl LadspaEffect {
+    pub fn new(plugin: Plugin) -> Result<Self, LadspaError> {
+        let audio_inputs = plugin.audio_ports(true);
+        let audio_outputs = plugin.audio_ports(false);
+
+        let mut effect = Self {
+            plugin,
+            instances: vec![],
+            control_inputs: plugin.control_inputs().collect(),
+            inputs: [vec![0.; MAX_FRAMES].into(), vec![0.; MAX_FRAMES].into()],
+            outputs: [vec![0.; MAX_FRAMES].into(), vec![0.; MAX_FRAMES].into()],
+        };
+
+        match (audio_inputs.as_slice(), audio_outputs.as_slice()) {
+            ([input], [output]) => {
+                for channel in 0..2 {
+                    let instance = effect.instantiate()?;
+                    effect.connect(&instance, *input, *output, channel);
+                    effect.instances.push(instance);
+                }
+            }
+            ([left_input, right_input], [left_output, right_output]) => {
+                let instance = effect.instantiate()?;
+                effect.connect(&instance, *left_input, *left_output, 0);
+                effect.connect(&instance, *right_input, *right_output, 1);
+                effect.instances.push(instance);
+            }
+            _ => return Err(LadspaError::UnsupportedChannels),
+        }
+
+        for instance in &effect.instances {
+            if let Some(activate) = effect.plugin.descriptor.activate {
+                unsafe { activate(instance.handle) };
+            }
+        }
+
+        Ok(effect)
+    }
+
+    fn instantiate(&self) -> Result<Instance, LadspaError> {
+        let descriptor = self.plugin.descriptor;
+        let handle = unsafe { (descriptor.instantiate)(descriptor, SAMPLE_RATE as c_ulong) };
+
+        if handle.is_null() {
+            return Err(LadspaError::Instantiate);
+        }
+
+        let mut instance = Instance {
+            handle,
+            controls: vec![0.; descriptor.port_count as usize].into(),
+        };
+
+        // Output control ports have to be connected as well, even though we don't read them
+        let control_ports: Vec<_> = self
+            .plugin
+            .ports()
+            .filter(|(_, d)| d & PORT_CONTROL != 0)
+            .map(|(index, _)| index)
+            .collect();
+
+        for port in control_ports {
+            let location = &mut instance.controls[port] as *mut f32;
+            unsafe { (descriptor.connect_port)(handle, port as c_ulong, location) };
+        }
+
+        Ok(instance)
+    }
+
+    /// Connects the buffers of a channel to the ports of an instance.
+    /// The buffers are boxed, so they don't move when the effect does.
+    fn connect(&mut self, instance: &Instance, input: usize, output: usize, channel: usize) {
+        let connect_port = self.plugin.descriptor.connect_port;
+
+        unsafe {
+            connect_port(
+                instance.handle,
+                input as c_ulong,
+                self.inputs[channel].as_mut_ptr(),
+            );
+            connect_port(
+                instance.handle,
+                output as c_ulong,
+                self.outputs[channel].as_mut_ptr(),
+            );
+        }
+    }
+}
+
+impl Effect for LadspaEffect {
+    fn process(&mut self, samples: &mut [Sample], _: &EffectContext) {
+        for chunk in samples.chunks_mut(MAX_FRAMES * 2) {
+            let frames = chunk.len() / 2;
+            let [left_input, right_input] = &mut self.inputs;
+
+            kernels::deinterleave(chunk, left_input, right_input);
+
+            for instance in &self.instances {
+                unsafe { (self.plugin.descriptor.run)(instance.handle, frames as c_ulong) };
+            }
+
+            let [left_output, right_output] = &self.outputs;
+            kernels::interleave(&left_output[..frames], &right_output[..frames], chunk);
+        }
+    }
+
+    fn set_parameters(&mut self, values: &[f32]) {
+        for instance in self.instances.iter_mut() {
+            for (port, value) in self.control_inputs.iter().zip(values) {
+                instance.controls[*port] = *value;
+            }
+        }
+    }
+}
+
+impl Drop for LadspaEffect {
+    fn drop(&mut self) {
+        let descriptor = self.plugin.descriptor;
+
+        for instance in &self.instances {
+            unsafe {
+                if let Some(deactivate) = descriptor.deactivate {
+                    deactivate(instance.handle);
+                }
+
+                (descriptor.cleanup)(instance.handle);
+            }
+        }
+    }
+}
+
+/// Resolves plugin paths the same way other LADSPA hosts do, by searching LADSPA_PATH for relative paths
+fn resolve_path(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_owned());
+    }
+
+    let search_path = env::var("LADSPA_PATH").unwrap_or_else(|_| DEFAULT_SEARCH_PATH.to_owned());
+
+    search_path
+        .split(':')
+        .map(|directory| Path::new(directory).join(path))
+        .find(|candidate| candidate.exists())
+}
@@ -0,0 +1,45 @@
+use crate::audio::{Sample, SAMPLE_RATE};
+
+use super::{db_to_gain, Effect, EffectContext};
+
+/// Keeps peaks below a threshold, clamping instantly and recovering over the release time
+pub struct Limiter {
+    threshold: f32,
+    /// How much of the remaining gain reduction is kept per frame
+    release: f32,
+    gain: f32,
+}
+
+impl Effect for Limiter {
+    fn process(&mut self, samples: &mut [Sample], _: &EffectContext) {
+        for frame in samples.chunks_exact_mut(2) {
+            let peak = frame[0].abs().max(frame[1].abs());
+
+            self.gain = 1. - (1. - self.gain) * self.release;
+
+            if peak * self.gain > self.threshold {
+                self.gain = self.threshold / peak;
+            }
+
+            frame[0] *= self.gain;
+            frame[1] *= self.gain;
+        }
+    }
+
+    fn set_parameters(&mut self, values: &[f32]) {
+        let release_frames = values[1] / 1000. * SAMPLE_RATE as f32;
+
+        self.threshold = db_to_gain(values[0]);
+        self.release = (-1. / release_frames).exp();
+    }
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self {
+            threshold: 1.,
+            release: 0.,
+            gain: 1.,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+mod filter;
+mod gain;
+mod ladspa;
+mod limiter;
+mod normalize;
+
+use crate::state::{EffectConfig, EffectKind};
+
+use self::{
+    filter::Filter,
+    gain::Gain,
+    ladspa::{LadspaEffect, Plugin},
+    limiter::Limiter,
+    normalize::Normalize,
+};
+
+use super::Sample;
+
+pub(in crate::audio) use self::filter::Biquad;
+
+/// A processing stage in the [EffectChain]
+pub trait Effect: Send {
+    /// Processes interleaved stereo samples in place
+    fn process(&mut self, samples: &mut [Sample], context: &EffectContext);
+
+    /// Receives the parameter values in the order of [EffectKind::parameters], whenever they change
+    fn set_parameters(&mut self, values: &[f32]);
+}
+
+/// Information about the stream that effects may need
+pub struct EffectContext {
+    /// Volume of the current source
+    pub source_volume: f32,
+}
+
+/// Describes a parameter of an effect, so it can be edited
+#[derive(Debug, Clone)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub unit: &'static str,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    /// How much the value changes per adjustment
+    pub step: f32,
+}
+
+/// Runs the effects configured in [crate::state::AudioConfig] in order
+#[derive(Default)]
+pub struct EffectChain {
+    stages: Vec<Stage>,
+}
+
+/// A change to the [EffectChain], worked out away from the audio thread so applying it is cheap
+pub struct EffectUpdate(UpdateKind);
+
+enum UpdateKind {
+    /// The effects themselves changed, so the whole chain is replaced
+    Rebuild(Vec<Stage>),
+    /// Whether each stage is bypassed, and its new parameter values if they changed
+    Parameters(Vec<(bool, Option<Vec<f32>>)>),
+}
+
+struct Stage {
+    /// Missing when the effect couldn't be created, in which case the stage does nothing
+    effect: Option<Box<dyn Effect>>,
+    bypassed: bool,
+}
+
+impl EffectChain {
+    /// Applies a prepared update without allocating or loading anything.
+    /// Returns the update, which holds the replaced stages, so it can be dropped elsewhere.
+    pub fn apply(&mut self, mut update: EffectUpdate) -> EffectUpdate {
+        match &mut update.0 {
+            UpdateKind::Rebuild(stages) => std::mem::swap(&mut self.stages, stages),
+            UpdateKind::Parameters(changes) => {
+                for (stage, (bypassed, values)) in self.stages.iter_mut().zip(changes.iter()) {
+                    stage.bypassed = *bypassed;
+
+                    if let (Some(effect), Some(values)) = (&mut stage.effect, values) {
+                        effect.set_parameters(values);
+                    }
+                }
+            }
+        }
+
+        update
+    }
+
+    pub fn process(&mut self, samples: &mut [Sample], context: &EffectContext) {
+        let effects = self
+            .stages
+            .iter_mut()
+            .filter(|s| !s.bypassed)
+            .filter_map(|s| s.effect.as_mut());
+
+        for effect in effects {
+            effect.process(samples, context);
+        }
+    }
+}
+
+impl EffectUpdate {
+    /// Works out how to bring a chain built from `old` up to date with `new`, only rebuilding it if the effects themselves changed.
+    /// This loads plugins and allocates, so it shouldn't be called from the audio thread.
+    pub fn new(old: &[EffectConfig], new: &[EffectConfig]) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        let is_same_layout =
+            old.len() == new.len() && old.iter().zip(new).all(|(old, new)| old.kind == new.kind);
+
+        let kind = if is_same_layout {
+            let changes = old
+                .iter()
+                .zip(new)
+                .map(|(old, new)| {
+                    let values = (old.parameters != new.parameters).then(|| new.values());
+                    (new.bypassed, values)
+                })
+                .collect();
+
+            UpdateKind::Parameters(changes)
+        } else {
+            UpdateKind::Rebuild(new.iter().map(Stage::new).collect())
+        };
+
+        Some(Self(kind))
+    }
+}
+
+impl Stage {
+    fn new(config: &EffectConfig) -> Self {
+        let effect: Option<Box<dyn Effect>> = match &config.kind {
+            EffectKind::Normalize => Some(Box::new(Normalize)),
+            EffectKind::Gain => Some(Box::<Gain>::default()),
+            EffectKind::Filter => Some(Box::<Filter>::default()),
+            EffectKind::Limiter => Some(Box::<Limiter>::default()),
+            EffectKind::Ladspa { path, label } => Plugin::load(path, label)
+                .and_then(LadspaEffect::new)
+                .ok()
+                .map(|e| Box::new(e) as Box<dyn Effect>),
+        };
+
+        let effect = effect.map(|mut effect| {
+            effect.set_parameters(&config.values());
+            effect
+        });
+
+        Self {
+            effect,
+            bypassed: config.bypassed,
+        }
+    }
+}
+
+impl EffectKind {
+    pub fn name(&self) -> String {
+        match self {
+            EffectKind::Normalize => "Normalize",
+            EffectKind::Gain => "Gain",
+            EffectKind::Filter => "Filter",
+            EffectKind::Limiter => "Limiter",
+            EffectKind::Ladspa { path, label } => {
+                return Plugin::load(path, label)
+                    .map(|p| p.name())
+                    .unwrap_or_else(|_| label.clone())
+            }
+        }
+        .to_owned()
+    }
+
+    /// Returns why the effect can't be used, if it can't
+    pub fn error(&self) -> Option<String> {
+        match self {
+            EffectKind::Ladspa { path, label } => {
+                Plugin::load(path, label).err().map(|e| e.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn parameters(&self) -> Vec<ParameterInfo> {
+        match self {
+            EffectKind::Normalize => vec![],
+            EffectKind::Gain => vec![ParameterInfo::new("Gain", "dB", 0., -24., 24., 0.5)],
+            EffectKind::Filter => vec![
+                ParameterInfo::new(
+                    "Low Cut",
+                    "Hz",
+                    filter::MIN_FREQUENCY,
+                    filter::MIN_FREQUENCY,
+                    2000.,
+                    10.,
+                ),
+                ParameterInfo::new(
+                    "High Cut",
+                    "Hz",
+                    filter::MAX_FREQUENCY,
+                    1000.,
+                    filter::MAX_FREQUENCY,
+                    500.,
+                ),
+            ],
+            EffectKind::Limiter => vec![
+                ParameterInfo::new("Threshold", "dB", -1., -24., 0., 0.5),
+                ParameterInfo::new("Release", "ms", 100., 10., 1000., 10.),
+            ],
+            EffectKind::Ladspa { path, label } => Plugin::load(path, label)
+                .map(|p| p.parameters())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl EffectConfig {
+    /// Returns the value of every parameter, falling back to defaults for the ones that aren't set
+    pub fn values(&self) -> Vec<f32> {
+        self.kind
+            .parameters()
+            .iter()
+            .map(|info| self.value(info))
+            .collect()
+    }
+
+    pub fn value(&self, info: &ParameterInfo) -> f32 {
+        self.parameters
+            .get(&info.name)
+            .copied()
+            .unwrap_or(info.default)
+            .clamp(info.min, info.max)
+    }
+}
+
+impl ParameterInfo {
+    fn new(name: &str, unit: &'static str, default: f32, min: f32, max: f32, step: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            unit,
+            default,
+            min,
+            max,
+            step,
+        }
+    }
+}
+
+/// Converts decibels to a linear gain factor
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.)
+}
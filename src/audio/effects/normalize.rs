@@ -0,0 +1,18 @@
+use crate::audio::{kernels, Sample};
+
+use super::{Effect, EffectContext};
+
+/// Makes up for the volume of the source, so it is streamed at full volume
+pub struct Normalize;
+
+impl Effect for Normalize {
+    fn process(&mut self, samples: &mut [Sample], context: &EffectContext) {
+        let reciprocal = 1. / context.source_volume;
+        let db_loudness = 10. * reciprocal.log(3.);
+        let signal_factor = 10f32.powf(db_loudness / 20.);
+
+        kernels::apply_gain(samples, signal_factor);
+    }
+
+    fn set_parameters(&mut self, _: &[f32]) {}
+}
@@ -0,0 +1,186 @@
+use std::io::{self, Read, Seek};
+
+use audiopus::{coder::Encoder, Application, Bitrate, Channels, SampleRate};
+use parking_lot::Mutex;
+use songbird::input::reader::MediaSource;
+
+use crate::state::{EncoderConfig, EncoderMode};
+
+use super::{analysis::read_samples, AudioStream, Sample, SAMPLE_IN_BYTES, SAMPLE_RATE};
+
+/// Discord expects 20ms of stereo audio per packet
+const FRAME_SIZE: usize = SAMPLE_RATE / 50 * 2 * SAMPLE_IN_BYTES;
+
+/// The largest packet size recommended by Opus
+const MAX_PACKET_SIZE: usize = 4000;
+
+/// Used when the voice channel doesn't tell us its bitrate
+const DEFAULT_CHANNEL_BITRATE: u32 = 64_000;
+const MIN_BITRATE: u32 = 6_000;
+const MAX_BITRATE: u32 = 510_000;
+
+/// The packet loss in-band FEC is tuned for, in percent
+const FEC_PACKET_LOSS: u8 = 10;
+
+/// Encodes the stream with our own settings instead of songbird's defaults
+pub struct OpusEncoder {
+    encoder: Encoder,
+    config: EncoderConfig,
+
+    /// The highest bitrate the voice channel allows, in bits per second
+    channel_bitrate: u32,
+}
+
+impl OpusEncoder {
+    pub fn new(
+        config: &EncoderConfig,
+        channel_bitrate: Option<u64>,
+    ) -> Result<Self, audiopus::Error> {
+        let channel_bitrate = channel_bitrate
+            .map(|b| b as u32)
+            .unwrap_or(DEFAULT_CHANNEL_BITRATE);
+
+        let mut encoder = Self {
+            encoder: create_encoder(config.mode)?,
+            config: config.clone(),
+            channel_bitrate,
+        };
+
+        encoder.apply()?;
+        Ok(encoder)
+    }
+
+    /// Applies the config if it changed since the last time
+    pub fn configure(&mut self, config: &EncoderConfig) -> Result<(), audiopus::Error> {
+        if *config == self.config {
+            return Ok(());
+        }
+
+        // The application mode can't be changed once the encoder is running
+        if config.mode != self.config.mode {
+            self.encoder = create_encoder(config.mode)?;
+        }
+
+        self.config = config.clone();
+        self.apply()
+    }
+
+    /// The bitrate the encoder is using, in bits per second
+    pub fn bitrate(&self) -> u32 {
+        let requested = self
+            .config
+            .bitrate_kbps
+            .map(|kbps| kbps * 1000)
+            .unwrap_or(self.channel_bitrate);
+
+        requested
+            .min(self.channel_bitrate)
+            .clamp(MIN_BITRATE, MAX_BITRATE)
+    }
+
+    pub fn encode(&self, samples: &[Sample], packet: &mut [u8]) -> Result<usize, audiopus::Error> {
+        self.encoder.encode_float(samples, packet)
+    }
+
+    fn apply(&mut self) -> Result<(), audiopus::Error> {
+        let packet_loss = if self.config.fec { FEC_PACKET_LOSS } else { 0 };
+        let bitrate = self.bitrate();
+
+        self.encoder
+            .set_bitrate(Bitrate::BitsPerSecond(bitrate as i32))?;
+        self.encoder
+            .set_complexity(self.config.complexity.min(10))?;
+        self.encoder.set_inband_fec(self.config.fec)?;
+        self.encoder.set_packet_loss_perc(packet_loss)
+    }
+}
+
+fn create_encoder(mode: EncoderMode) -> Result<Encoder, audiopus::Error> {
+    let application = match mode {
+        EncoderMode::Audio => Application::Audio,
+        EncoderMode::LowDelay => Application::LowDelay,
+    };
+
+    Encoder::new(SampleRate::Hz48000, Channels::Stereo, application)
+}
+
+/// Reads the stream as DCA framed Opus packets, which songbird can pass through to Discord as is
+pub struct EncodedStream {
+    stream: AudioStream,
+
+    /// Opus encoders can't be shared between threads, which songbird requires
+    encoder: Mutex<OpusEncoder>,
+
+    pcm: Vec<u8>,
+    samples: Vec<Sample>,
+
+    /// The current packet, including its length prefix
+    packet: Vec<u8>,
+    position: usize,
+}
+
+impl EncodedStream {
+    pub fn new(stream: AudioStream, encoder: OpusEncoder) -> Self {
+        Self {
+            stream,
+            encoder: encoder.into(),
+            pcm: vec![0; FRAME_SIZE],
+            samples: Vec::with_capacity(FRAME_SIZE / SAMPLE_IN_BYTES),
+            packet: Vec::with_capacity(MAX_PACKET_SIZE + 2),
+            position: 0,
+        }
+    }
+
+    fn next_packet(&mut self) -> io::Result<()> {
+        self.stream.read_exact(&mut self.pcm)?;
+        read_samples(&self.pcm, &mut self.samples);
+
+        let config = self.stream.encoder_config();
+        let encoder = self.encoder.get_mut();
+        encoder.configure(&config).map_err(io::Error::other)?;
+
+        self.packet.resize(MAX_PACKET_SIZE + 2, 0);
+
+        let length = encoder
+            .encode(&self.samples, &mut self.packet[2..])
+            .map_err(io::Error::other)?;
+
+        self.packet[..2].copy_from_slice(&(length as i16).to_le_bytes());
+        self.packet.truncate(length + 2);
+        self.position = 0;
+
+        Ok(())
+    }
+}
+
+impl Read for EncodedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.packet.len() {
+            self.next_packet()?;
+        }
+
+        let remaining = &self.packet[self.position..];
+        let length = remaining.len().min(buf.len());
+
+        buf[..length].copy_from_slice(&remaining[..length]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+impl Seek for EncodedStream {
+    fn seek(&mut self, _: io::SeekFrom) -> io::Result<u64> {
+        unreachable!()
+    }
+}
+
+impl MediaSource for EncodedStream {
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+
+    fn is_seekable(&self) -> bool {
+        false
+    }
+}
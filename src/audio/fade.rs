@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use super::{Sample, SAMPLE_RATE};
+
+/// A linear gain ramp applied to interleaved stereo samples
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    gain: f32,
+    target: f32,
+
+    /// How much the gain changes per frame
+    step: f32,
+}
+
+impl Fade {
+    /// Creates a fade that is resting at the given gain
+    pub fn new(gain: f32) -> Self {
+        Self {
+            gain,
+            target: gain,
+            step: 0.,
+        }
+    }
+
+    /// Starts from silence and ramps up to unity gain
+    pub fn fade_in(&mut self, duration: Duration) {
+        self.gain = 0.;
+        self.ramp_to(1., duration);
+    }
+
+    /// Ramps down to silence from the current gain
+    pub fn fade_out(&mut self, duration: Duration) {
+        self.ramp_to(0., duration);
+    }
+
+    pub fn ramp_to(&mut self, target: f32, duration: Duration) {
+        let frames = (duration.as_secs_f32() * SAMPLE_RATE as f32).max(1.);
+
+        self.target = target;
+        self.step = (target - self.gain) / frames;
+    }
+
+    /// Returns true while the gain is still moving towards its target
+    pub fn is_active(&self) -> bool {
+        self.gain != self.target
+    }
+
+    /// Returns true when the fade has settled at zero gain
+    pub fn is_silent(&self) -> bool {
+        !self.is_active() && self.gain == 0.
+    }
+
+    pub fn apply(&mut self, samples: &mut [Sample]) {
+        if !self.is_active() {
+            if self.gain != 1. {
+                samples.iter_mut().for_each(|s| *s *= self.gain);
+            }
+
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(2) {
+            let gain = self.advance();
+
+            frame[0] *= gain;
+            frame[1] *= gain;
+        }
+    }
+
+    /// Moves the gain one frame closer to the target, and returns it
+    pub fn advance(&mut self) -> f32 {
+        let next = self.gain + self.step;
+
+        let has_arrived = (self.step > 0. && next >= self.target)
+            || (self.step < 0. && next <= self.target)
+            || self.step == 0.;
+
+        self.gain = if has_arrived { self.target } else { next };
+        self.gain
+    }
+}
+
+impl Default for Fade {
+    fn default() -> Self {
+        Self::new(1.)
+    }
+}
@@ -0,0 +1,79 @@
+use std::f32::consts::PI;
+
+/// An in-place radix-2 FFT of a fixed size
+pub struct Fft {
+    size: usize,
+    /// `e^(-2πik/size)` for the first half of the circle
+    twiddles: Vec<(f32, f32)>,
+    /// Where each index ends up after bit reversal
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// Creates an FFT for the given size, which has to be a power of two
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT size is a power of two");
+
+        let bits = size.trailing_zeros();
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2. * PI * k as f32 / size as f32;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+
+        let reversed = (0..size)
+            .map(|i: usize| {
+                i.reverse_bits()
+                    .checked_shr(usize::BITS - bits)
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        Self {
+            size,
+            twiddles,
+            reversed,
+        }
+    }
+
+    /// Transforms the real and imaginary parts in place
+    pub fn process(&self, real: &mut [f32], imag: &mut [f32]) {
+        assert_eq!(real.len(), self.size);
+        assert_eq!(imag.len(), self.size);
+
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                real.swap(i, j);
+                imag.swap(i, j);
+            }
+        }
+
+        let mut length = 2;
+
+        while length <= self.size {
+            let half = length / 2;
+            let stride = self.size / length;
+
+            for start in (0..self.size).step_by(length) {
+                for k in 0..half {
+                    let (cos, sin) = self.twiddles[k * stride];
+
+                    let a = start + k;
+                    let b = a + half;
+
+                    let re = real[b] * cos - imag[b] * sin;
+                    let im = real[b] * sin + imag[b] * cos;
+
+                    real[b] = real[a] - re;
+                    imag[b] = imag[a] - im;
+                    real[a] += re;
+                    imag[a] += im;
+                }
+            }
+
+            length *= 2;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::VecDeque, time::Duration};
+
+use super::{Sample, SAMPLE_RATE};
+
+/// How many points are kept, one for every second, which is 10 minutes
+pub const HISTORY_LENGTH: usize = 600;
+
+/// Keeps track of how loud the stream was over the session
+pub struct History {
+    points: VecDeque<HistoryPoint>,
+
+    /// The highest sample in the current second
+    peak: Sample,
+    frames: usize,
+
+    /// Whether a point was added since the history was last published
+    changed: bool,
+}
+
+/// How loud a second of the stream was, negative infinity when it was silent
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryPoint {
+    /// The short-term loudness at the end of the second, in LUFS
+    pub short_term: f32,
+    /// The highest sample peak, in dBFS
+    pub peak: f32,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            points: VecDeque::with_capacity(HISTORY_LENGTH),
+            peak: 0.,
+            frames: 0,
+            changed: false,
+        }
+    }
+
+    /// Adds interleaved stereo samples, along with the current short-term loudness
+    pub fn write(&mut self, samples: &[Sample], short_term: f32) {
+        for frame in samples.chunks_exact(2) {
+            self.peak = self.peak.max(frame[0].abs()).max(frame[1].abs());
+            self.frames += 1;
+
+            if self.frames == SAMPLE_RATE {
+                self.push(HistoryPoint {
+                    short_term,
+                    peak: 20. * self.peak.log10(),
+                });
+
+                self.peak = 0.;
+                self.frames = 0;
+            }
+        }
+    }
+
+    /// Fills in the time nothing was analyzed with silence
+    pub fn skip(&mut self, duration: Duration) {
+        let seconds = (duration.as_secs() as usize).min(HISTORY_LENGTH);
+
+        for _ in 0..seconds {
+            self.push(HistoryPoint::SILENT);
+        }
+    }
+
+    fn push(&mut self, point: HistoryPoint) {
+        if self.points.len() == HISTORY_LENGTH {
+            self.points.pop_front();
+        }
+
+        self.points.push_back(point);
+        self.changed = true;
+    }
+
+    /// Copies the points, from oldest to newest, when any were added since the last time
+    pub fn publish(&mut self, points: &mut Vec<HistoryPoint>) {
+        if !self.changed {
+            return;
+        }
+
+        points.clear();
+        points.extend(&self.points);
+        self.changed = false;
+    }
+}
+
+impl HistoryPoint {
+    const SILENT: Self = Self {
+        short_term: f32::NEG_INFINITY,
+        peak: f32::NEG_INFINITY,
+    };
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
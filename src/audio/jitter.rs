@@ -0,0 +1,266 @@
+use std::{sync::Arc, time::Duration};
+
+use crossbeam::atomic::AtomicCell;
+use ringbuf::HeapConsumer;
+
+use super::{
+    analysis::sample_from_bytes, duration_in_bytes, duration_of_bytes, fade::Fade, Sample,
+    LATENCY_SIZE, MAX_JITTER_SIZE, SAMPLE_IN_BYTES,
+};
+
+const STEREO: usize = SAMPLE_IN_BYTES * 2;
+
+/// The least amount of audio the buffer aims to hold, it can grow up to [MAX_JITTER_SIZE]
+const MIN_TARGET: Duration = Duration::from_millis(20);
+const TARGET_STEP: Duration = Duration::from_millis(10);
+
+/// How many reads in a row have to go by without an underrun before the target shrinks
+const READS_BEFORE_SHRINK: usize = 500;
+
+/// How long it takes for concealed audio to decay into silence
+const CONCEALMENT_LENGTH: Duration = Duration::from_millis(5);
+
+/// How long it takes for audio to come back after recovering from an underrun
+const RESUME_FADE: Duration = Duration::from_millis(10);
+
+/// Keeps the amount of buffered audio steady, and covers up underruns
+#[derive(Clone)]
+pub struct JitterBuffer {
+    /// The amount of audio to keep buffered on top of the delay, in bytes
+    target: usize,
+
+    /// Whether the buffer is filling back up to the target before playing
+    is_priming: bool,
+    stable_reads: usize,
+
+    /// The delay from the previous read, to tell apart overruns from the delay shrinking
+    last_delay: usize,
+    last_frame: [Sample; 2],
+
+    fade: Fade,
+    resume_fade: Duration,
+
+    /// The amount of reset requests that have been handled
+    handled_resets: u64,
+
+    health: Arc<BufferHealth>,
+}
+
+/// Statistics about the buffer, shared with the interface
+#[derive(Debug, Default)]
+pub struct BufferHealth {
+    fill: AtomicCell<usize>,
+    target: AtomicCell<usize>,
+    underruns: AtomicCell<u64>,
+    overruns: AtomicCell<u64>,
+
+    /// Incremented to make the reader start over, such as when resuming from a pause
+    resets: AtomicCell<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BufferStats {
+    pub latency: Duration,
+    pub target: Duration,
+    pub underruns: u64,
+    pub overruns: u64,
+    /// Chunks of audio that were dropped before reaching the buffer
+    pub dropped: u64,
+}
+
+impl JitterBuffer {
+    pub fn new(health: Arc<BufferHealth>) -> Self {
+        Self {
+            target: LATENCY_SIZE,
+            is_priming: true,
+            stable_reads: 0,
+            last_delay: 0,
+            last_frame: [0.; 2],
+            fade: Default::default(),
+            resume_fade: RESUME_FADE,
+            handled_resets: health.resets.load(),
+            health,
+        }
+    }
+
+    /// Starts over from an empty buffer, fading in once it is filled up
+    pub fn reset(&mut self, fade_in: Duration) {
+        self.is_priming = true;
+        self.resume_fade = fade_in;
+    }
+
+    /// Returns true once for every time a reset was requested through [BufferHealth]
+    pub fn take_reset_request(&mut self) -> bool {
+        let resets = self.health.resets.load();
+        let is_requested = resets != self.handled_resets;
+
+        self.handled_resets = resets;
+        is_requested
+    }
+
+    /// Fills the buffer with audio from the consumer, held back by the given delay in bytes
+    pub fn read(&mut self, consumer: &mut HeapConsumer<u8>, buf: &mut [u8], delay: usize) {
+        let available = consumer.len();
+        // Never wait for more than the ring can hold, or it would stay silent
+        let target = (delay + self.target).min(consumer.capacity());
+
+        let did_delay_shrink = delay < self.last_delay;
+        self.last_delay = delay;
+
+        self.health.fill.store(available.saturating_sub(delay));
+        self.health.target.store(self.target);
+
+        if self.is_priming {
+            if available < target.max(buf.len()) {
+                buf.fill(0);
+                return;
+            }
+
+            self.is_priming = false;
+            self.fade.fade_in(self.resume_fade);
+            self.resume_fade = RESUME_FADE;
+        }
+
+        // Too much audio has piled up, so skip ahead to catch up
+        if available > target + self.target {
+            let excess = (available - target) / STEREO * STEREO;
+            consumer.skip(excess);
+
+            if !did_delay_shrink {
+                self.health.overruns.fetch_add(1);
+            }
+        }
+
+        if consumer.len() >= buf.len() {
+            consumer.pop_slice(buf);
+            self.remember_last_frame(buf);
+
+            self.stable_reads += 1;
+
+            if self.stable_reads >= READS_BEFORE_SHRINK {
+                self.stable_reads = 0;
+                self.target = self
+                    .target
+                    .saturating_sub(duration_in_bytes(TARGET_STEP))
+                    .max(duration_in_bytes(MIN_TARGET));
+            }
+        } else {
+            let readable = consumer.len() / STEREO * STEREO;
+            let read = consumer.pop_slice(&mut buf[..readable]);
+
+            self.remember_last_frame(&buf[..read]);
+            self.conceal(&mut buf[read..]);
+
+            self.health.underruns.fetch_add(1);
+
+            self.stable_reads = 0;
+            self.is_priming = true;
+            self.target = (self.target + duration_in_bytes(TARGET_STEP)).min(MAX_JITTER_SIZE);
+        }
+
+        if self.fade.is_active() {
+            for frame in buf.chunks_exact_mut(STEREO) {
+                let gain = self.fade.advance();
+
+                for bytes in frame.chunks_exact_mut(SAMPLE_IN_BYTES) {
+                    let sample = sample_from_bytes(bytes) * gain;
+                    bytes.copy_from_slice(&sample.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    fn remember_last_frame(&mut self, buf: &[u8]) {
+        if buf.len() < STEREO {
+            return;
+        }
+
+        let frame = &buf[buf.len() - STEREO..];
+
+        self.last_frame = [
+            sample_from_bytes(&frame[..SAMPLE_IN_BYTES]),
+            sample_from_bytes(&frame[SAMPLE_IN_BYTES..]),
+        ];
+    }
+
+    /// Fills the missing audio by letting the last frame decay into silence
+    fn conceal(&mut self, buf: &mut [u8]) {
+        let length = duration_in_bytes(CONCEALMENT_LENGTH) / STEREO;
+
+        for (index, frame) in buf.chunks_exact_mut(STEREO).enumerate() {
+            let gain = 1. - (index as f32 / length as f32).min(1.);
+
+            frame[..SAMPLE_IN_BYTES].copy_from_slice(&(self.last_frame[0] * gain).to_le_bytes());
+            frame[SAMPLE_IN_BYTES..].copy_from_slice(&(self.last_frame[1] * gain).to_le_bytes());
+        }
+
+        self.last_frame = [0.; 2];
+    }
+}
+
+impl BufferHealth {
+    pub fn request_reset(&self) {
+        self.resets.fetch_add(1);
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        BufferStats {
+            latency: duration_of_bytes(self.fill.load()),
+            target: duration_of_bytes(self.target.load()),
+            underruns: self.underruns.load(),
+            overruns: self.overruns.load(),
+            dropped: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use ringbuf::{HeapProducer, HeapRb};
+
+    use super::*;
+    use crate::audio::{BUFFER_SIZE, MAX_DELAY_SIZE};
+
+    /// Feeds the buffer in step with reading from it, until audio comes out
+    fn plays(
+        jitter: &mut JitterBuffer,
+        producer: &mut HeapProducer<u8>,
+        consumer: &mut HeapConsumer<u8>,
+        audio: &[u8],
+    ) -> bool {
+        let mut buf = vec![0; audio.len()];
+
+        (0..500).any(|_| {
+            producer.push_slice(audio);
+            jitter.read(consumer, &mut buf, MAX_DELAY_SIZE);
+            buf.iter().any(|&b| b != 0)
+        })
+    }
+
+    #[test]
+    fn resumes_after_underrun_at_max_delay() {
+        let (mut producer, mut consumer) = HeapRb::<u8>::new(BUFFER_SIZE).split();
+        let mut jitter = JitterBuffer::new(Default::default());
+
+        let chunk = duration_in_bytes(Duration::from_millis(20));
+        let audio: Vec<u8> = iter::repeat_n(0.5f32.to_le_bytes(), chunk / SAMPLE_IN_BYTES)
+            .flatten()
+            .collect();
+
+        // One more underrun grows the target to its largest
+        jitter.target = MAX_JITTER_SIZE - duration_in_bytes(TARGET_STEP);
+
+        assert!(plays(&mut jitter, &mut producer, &mut consumer, &audio));
+
+        let mut buf = vec![0; chunk];
+
+        while jitter.health.underruns.load() == 0 {
+            jitter.read(&mut consumer, &mut buf, MAX_DELAY_SIZE);
+        }
+
+        assert_eq!(jitter.target, MAX_JITTER_SIZE);
+        assert!(plays(&mut jitter, &mut producer, &mut consumer, &audio));
+    }
+}
@@ -0,0 +1,92 @@
+//! Hot loops of the audio path, compiled for every SIMD instruction set we can detect at runtime.
+//!
+//! The loops are written so the compiler can vectorize them, which is why some of them
+//! work on fixed lanes instead of using the more obvious iterator chains.
+//! This module only depends on `std` so the benchmarks can include it directly.
+
+use multiversion::multiversion;
+
+/// Amount of independent accumulators used by reductions
+const LANES: usize = 8;
+
+/// Converts little endian f32 bytes into samples.
+/// Converts as many samples as fit in both slices.
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn bytes_to_samples(bytes: &[u8], samples: &mut [f32]) {
+    for (sample, bytes) in samples.iter_mut().zip(bytes.chunks_exact(4)) {
+        *sample = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+}
+
+/// Converts samples into little endian f32 bytes.
+/// Converts as many samples as fit in both slices.
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn samples_to_bytes(samples: &[f32], bytes: &mut [u8]) {
+    for (sample, bytes) in samples.iter().zip(bytes.chunks_exact_mut(4)) {
+        bytes.copy_from_slice(&sample.to_le_bytes());
+    }
+}
+
+/// Multiplies every sample by `gain`
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn apply_gain(samples: &mut [f32], gain: f32) {
+    for sample in samples.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Splits interleaved stereo samples into a left and a right channel
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn deinterleave(samples: &[f32], left: &mut [f32], right: &mut [f32]) {
+    let frames = samples
+        .chunks_exact(2)
+        .zip(left.iter_mut().zip(right.iter_mut()));
+
+    for (frame, (l, r)) in frames {
+        *l = frame[0];
+        *r = frame[1];
+    }
+}
+
+/// Joins a left and a right channel into interleaved stereo samples
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn interleave(left: &[f32], right: &[f32], samples: &mut [f32]) {
+    let frames = samples
+        .chunks_exact_mut(2)
+        .zip(left.iter().zip(right.iter()));
+
+    for (frame, (l, r)) in frames {
+        frame[0] = *l;
+        frame[1] = *r;
+    }
+}
+
+/// Returns the highest absolute sample value
+#[multiversion(targets("x86_64+avx2", "x86_64+sse4.1", "aarch64+neon"))]
+pub fn peak(samples: &[f32]) -> f32 {
+    let mut lanes = [0f32; LANES];
+    let chunks = samples.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        for (lane, sample) in lanes.iter_mut().zip(chunk) {
+            *lane = faster_max(*lane, sample.abs());
+        }
+    }
+
+    let peak = remainder
+        .iter()
+        .fold(0f32, |acc, sample| faster_max(acc, sample.abs()));
+
+    lanes.into_iter().fold(peak, faster_max)
+}
+
+/// A max that doesn't care about NaN, which lets it vectorize
+#[inline(always)]
+fn faster_max(a: f32, b: f32) -> f32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+
+use super::{effects::Biquad, Sample, SAMPLE_RATE};
+
+/// Loudness is measured in blocks of 100 ms
+const BLOCK_FRAMES: usize = SAMPLE_RATE / 10;
+
+/// The momentary window is 400 ms, the short-term window is 3 s
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Gating blocks quieter than this are left out of the integrated loudness entirely
+const ABSOLUTE_GATE: f32 = -70.;
+/// Gating blocks this far below the ungated loudness are left out as well
+const RELATIVE_GATE: f32 = -10.;
+
+/// Measures loudness as described in ITU-R BS.1770
+pub struct LoudnessMeter {
+    state: State,
+    readout: Loudness,
+}
+
+/// Loudness readouts in LUFS, negative infinity when nothing was measured yet
+#[derive(Debug, Clone, Copy)]
+pub struct Loudness {
+    pub momentary: f32,
+    pub short_term: f32,
+    pub integrated: f32,
+}
+
+struct State {
+    /// The K-weighting filters of each channel, a high shelf followed by a highpass
+    filters: [[Biquad; 2]; 2],
+
+    /// Sum of the squared K-weighted samples of both channels in the current block
+    block_energy: f64,
+    block_frames: usize,
+
+    /// Mean square of the most recent blocks
+    blocks: VecDeque<f64>,
+
+    /// Mean square of every 400 ms gating block that passed the absolute gate
+    gating_blocks: Vec<f64>,
+    /// The same, but kept across resets
+    session_blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        Self {
+            state: State::new(),
+            readout: Loudness::SILENT,
+        }
+    }
+
+    pub fn write(&mut self, samples: &[Sample]) {
+        for frame in samples.chunks_exact(2) {
+            if let Some(loudness) = self.state.push(frame[0], frame[1]) {
+                self.readout = loudness;
+            }
+        }
+    }
+
+    /// Starts measuring the integrated loudness from scratch
+    pub fn reset(&mut self) {
+        let session_blocks = std::mem::take(&mut self.state.session_blocks);
+
+        self.state = State::new();
+        self.state.session_blocks = session_blocks;
+        self.readout = Loudness::SILENT;
+    }
+
+    pub fn loudness(&self) -> Loudness {
+        self.readout
+    }
+
+    /// The integrated loudness since the meter was created, regardless of resets
+    pub fn session_integrated(&self) -> f32 {
+        gated_loudness(&self.state.session_blocks)
+    }
+}
+
+impl State {
+    fn new() -> Self {
+        // The coefficients from the specification, which are for 48 kHz
+        let shelf = Biquad::new(
+            1.535_124_9,
+            -2.691_696_2,
+            1.198_392_8,
+            -1.690_659_3,
+            0.732_480_8,
+        );
+        let highpass = Biquad::new(1., -2., 1., -1.990_047_5, 0.990_072_25);
+
+        Self {
+            filters: [[shelf.clone(), highpass.clone()], [shelf, highpass]],
+            block_energy: 0.,
+            block_frames: 0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            gating_blocks: vec![],
+            session_blocks: vec![],
+        }
+    }
+
+    /// Adds a frame, returning new readouts whenever a block is finished
+    fn push(&mut self, left: Sample, right: Sample) -> Option<Loudness> {
+        for ([shelf, highpass], sample) in self.filters.iter_mut().zip([left, right]) {
+            let weighted = highpass.process(shelf.process(sample)) as f64;
+            self.block_energy += weighted * weighted;
+        }
+
+        self.block_frames += 1;
+
+        if self.block_frames < BLOCK_FRAMES {
+            return None;
+        }
+
+        if self.blocks.len() == SHORT_TERM_BLOCKS {
+            self.blocks.pop_front();
+        }
+
+        self.blocks
+            .push_back(self.block_energy / BLOCK_FRAMES as f64);
+        self.block_energy = 0.;
+        self.block_frames = 0;
+
+        let momentary = self.mean_of_recent(MOMENTARY_BLOCKS);
+        let short_term = self.mean_of_recent(SHORT_TERM_BLOCKS);
+
+        // Every momentary window is a gating block, as they overlap by 75%
+        if self.blocks.len() >= MOMENTARY_BLOCKS && to_lufs(momentary) > ABSOLUTE_GATE {
+            self.gating_blocks.push(momentary);
+            self.session_blocks.push(momentary);
+        }
+
+        Some(Loudness {
+            momentary: to_lufs(momentary),
+            short_term: to_lufs(short_term),
+            integrated: gated_loudness(&self.gating_blocks),
+        })
+    }
+
+    fn mean_of_recent(&self, amount: usize) -> f64 {
+        let amount = amount.min(self.blocks.len()).max(1);
+        self.blocks.iter().rev().take(amount).sum::<f64>() / amount as f64
+    }
+}
+
+/// The integrated loudness of gating blocks, after applying the relative gate
+fn gated_loudness(gating_blocks: &[f64]) -> f32 {
+    if gating_blocks.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+
+    let ungated = mean(gating_blocks.iter());
+    let threshold = to_lufs(ungated) + RELATIVE_GATE;
+
+    to_lufs(mean(
+        gating_blocks
+            .iter()
+            .filter(|&&block| to_lufs(block) > threshold),
+    ))
+}
+
+impl Loudness {
+    pub const SILENT: Self = Self {
+        momentary: f32::NEG_INFINITY,
+        short_term: f32::NEG_INFINITY,
+        integrated: f32::NEG_INFINITY,
+    };
+}
+
+fn mean<'a>(values: impl Iterator<Item = &'a f64>) -> f64 {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| (sum + value, count + 1));
+
+    if count == 0 {
+        0.
+    } else {
+        sum / count as f64
+    }
+}
+
+fn to_lufs(mean_square: f64) -> f32 {
+    if mean_square <= 0. {
+        return f32::NEG_INFINITY;
+    }
+
+    (-0.691 + 10. * mean_square.log10()) as f32
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    /// Interleaved stereo, with a 1 kHz sine at the level in dBFS on the given channels
+    fn sine(db: f32, seconds: f32, channels: [bool; 2]) -> Vec<Sample> {
+        let amplitude = 10f32.powf(db / 20.);
+        let frames = (SAMPLE_RATE as f32 * seconds) as usize;
+
+        (0..frames)
+            .flat_map(|i| {
+                let value = amplitude * (2. * PI * 1000. * i as f32 / SAMPLE_RATE as f32).sin();
+                channels.map(|on| if on { value } else { 0. })
+            })
+            .collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<Sample> {
+        vec![0.; (SAMPLE_RATE as f32 * seconds) as usize * 2]
+    }
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.1,
+            "expected about {expected}, got {value}"
+        );
+    }
+
+    #[test]
+    fn sine_in_one_channel_reads_3_lu_below_its_level() {
+        let mut meter = LoudnessMeter::new();
+        meter.write(&sine(-20., 5., [true, false]));
+
+        let loudness = meter.loudness();
+        assert_near(loudness.momentary, -23.);
+        assert_near(loudness.short_term, -23.);
+        assert_near(loudness.integrated, -23.);
+    }
+
+    #[test]
+    fn sine_in_both_channels_reads_its_level() {
+        let mut meter = LoudnessMeter::new();
+        meter.write(&sine(-20., 5., [true, true]));
+
+        assert_near(meter.loudness().integrated, -20.);
+    }
+
+    #[test]
+    fn absolute_gate_drops_silence() {
+        let mut meter = LoudnessMeter::new();
+
+        meter.write(&silence(5.));
+        assert_eq!(meter.loudness().integrated, f32::NEG_INFINITY);
+
+        // Long enough that the few blocks overlapping the silence barely count
+        meter.write(&sine(-20., 20., [true, false]));
+        meter.write(&silence(20.));
+        assert_near(meter.loudness().integrated, -23.);
+    }
+
+    #[test]
+    fn relative_gate_drops_quiet_blocks() {
+        let mut meter = LoudnessMeter::new();
+
+        // -53 LUFS passes the absolute gate, but is far below the loud part
+        meter.write(&sine(-20., 20., [true, false]));
+        meter.write(&sine(-50., 20., [true, false]));
+
+        // Averaging both halves would give about -26 LUFS
+        assert_near(meter.loudness().integrated, -23.);
+    }
+
+    #[test]
+    fn session_loudness_survives_resets() {
+        let mut meter = LoudnessMeter::new();
+
+        meter.write(&sine(-20., 5., [true, false]));
+        meter.reset();
+
+        assert_eq!(meter.loudness().integrated, f32::NEG_INFINITY);
+        assert_near(meter.session_integrated(), -23.);
+    }
+}
@@ -1,13 +1,37 @@
+mod agc;
 mod analysis;
+mod ducking;
+pub mod effects;
+mod encoder;
+mod fade;
+mod fft;
+mod history;
+mod jitter;
+mod kernels;
+mod loudness;
+mod pool;
 pub mod pulse;
+mod queue;
+mod resampler;
+mod scope;
+mod session;
 mod source;
+mod spectrum;
 mod system;
+mod transition;
+mod true_peak;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+pub use agc::AgcStats;
+pub use history::{HistoryPoint, HISTORY_LENGTH};
+pub use jitter::BufferStats;
+pub use loudness::Loudness;
 use parking_lot::Mutex;
 use ringbuf::{HeapConsumer, HeapProducer};
+pub use scope::Envelope;
 pub use source::*;
+pub use spectrum::Band;
 pub use system::*;
 
 pub type Sample = f32;
@@ -19,5 +43,30 @@ pub const SAMPLE_RATE: usize = 48000;
 pub const SAMPLE_IN_BYTES: usize = 4;
 
 pub const LATENCY_IN_SECONDS: f32 = 0.05;
-pub const BUFFER_SIZE: usize =
+pub const LATENCY_SIZE: usize =
     (SAMPLE_IN_BYTES * 2) * (SAMPLE_RATE as f32 * LATENCY_IN_SECONDS) as usize;
+
+/// The longest the stream can be delayed by, to sync it up with screen share
+pub const MAX_DELAY_IN_SECONDS: f32 = 2.;
+pub const MAX_DELAY_SIZE: usize =
+    (SAMPLE_IN_BYTES * 2) * (SAMPLE_RATE as f32 * MAX_DELAY_IN_SECONDS) as usize;
+
+/// The most audio the jitter buffer keeps on top of the delay
+pub const MAX_JITTER_IN_SECONDS: f32 = 0.25;
+pub const MAX_JITTER_SIZE: usize =
+    (SAMPLE_IN_BYTES * 2) * (SAMPLE_RATE as f32 * MAX_JITTER_IN_SECONDS) as usize;
+
+/// The buffer holds the delayed audio and the jitter buffer's target on top of the regular latency
+pub const BUFFER_SIZE: usize = LATENCY_SIZE + MAX_DELAY_SIZE + MAX_JITTER_SIZE;
+
+/// Returns the amount of bytes the given duration of stereo audio takes up
+pub fn duration_in_bytes(duration: Duration) -> usize {
+    let frames = (duration.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+    frames * SAMPLE_IN_BYTES * 2
+}
+
+/// Returns how long the given amount of bytes of stereo audio lasts
+pub fn duration_of_bytes(bytes: usize) -> Duration {
+    let frames = bytes / (SAMPLE_IN_BYTES * 2);
+    Duration::from_secs_f32(frames as f32 / SAMPLE_RATE as f32)
+}
@@ -0,0 +1,32 @@
+use crossbeam::queue::ArrayQueue;
+
+/// Recycles audio buffers, so chunks can be passed between threads without allocating each time
+pub struct BufferPool {
+    buffers: ArrayQueue<Vec<u8>>,
+    capacity: usize,
+}
+
+impl BufferPool {
+    pub fn new(size: usize, capacity: usize) -> Self {
+        let buffers = ArrayQueue::new(size);
+
+        for _ in 0..size {
+            let _ = buffers.push(Vec::with_capacity(capacity));
+        }
+
+        Self { buffers, capacity }
+    }
+
+    /// Takes an empty buffer out of the pool, or allocates one if the pool has run dry
+    pub fn take(&self) -> Vec<u8> {
+        self.buffers
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(self.capacity))
+    }
+
+    /// Puts a buffer back into the pool, dropping it if the pool is full
+    pub fn recycle(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        let _ = self.buffers.push(buffer);
+    }
+}
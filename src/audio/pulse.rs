@@ -4,12 +4,15 @@
 use std::{
     sync::{mpsc, Arc},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
 use crate::audio::SAMPLE_RATE;
-use crossbeam::channel::{unbounded, Receiver, Sender};
+use crossbeam::{
+    atomic::AtomicCell,
+    channel::{unbounded, Receiver, Sender},
+};
 use libpulse_binding::{
     callbacks::ListResult,
     context::{
@@ -27,7 +30,24 @@ use libpulse_binding::{
 };
 use parking_lot::{Mutex, RwLock};
 
-use super::BUFFER_SIZE;
+use super::{
+    pool::BufferPool,
+    queue::{AudioChunk, AudioQueue},
+    resampler::MIN_INPUT_RATE,
+    LATENCY_SIZE, SAMPLE_IN_BYTES,
+};
+
+/// How many chunks of audio can wait to be handled before the oldest ones are dropped
+const AUDIO_QUEUE_LENGTH: usize = 16;
+
+/// How many chunks of audio can be in flight before the pool has to allocate more
+const POOL_SIZE: usize = AUDIO_QUEUE_LENGTH * 2;
+
+/// How often peak streams report the level of a sink input
+const PEAK_RATE: u32 = 25;
+
+/// How long a peak is shown for when no new one arrives, which is the case when a sink input is corked
+const PEAK_LIFETIME: Duration = Duration::from_millis(250);
 
 /// Abstracts connections and interfacing with pulseaudio
 pub struct PulseClient {
@@ -38,6 +58,12 @@ pub struct PulseClient {
 
     pub(super) events: Receiver<PulseClientEvent>,
     event_sender: Sender<PulseClientEvent>,
+
+    /// Recorded audio ends up here, and should be recycled once handled
+    pub(super) audio: AudioQueue,
+
+    /// Used to tell apart audio from different recording streams
+    next_stream_id: AtomicCell<u32>,
 }
 
 impl PulseClient {
@@ -92,6 +118,11 @@ impl PulseClient {
             introspector,
             props,
             spec,
+            next_stream_id: Default::default(),
+            audio: AudioQueue::new(
+                AUDIO_QUEUE_LENGTH,
+                BufferPool::new(POOL_SIZE, LATENCY_SIZE).into(),
+            ),
         };
 
         Ok(client)
@@ -174,6 +205,7 @@ impl PulseClient {
 
                     let sink_input = SinkInput {
                         index: item.index,
+                        rate: item.sample_spec.rate,
                         props: item.proplist.clone(),
                         sink: item.sink,
                         name: item
@@ -202,20 +234,73 @@ impl PulseClient {
         Ok(result)
     }
 
+    /// How many chunks of audio were dropped because they weren't handled in time
+    pub fn dropped_chunks(&self) -> u64 {
+        self.audio.dropped()
+    }
+
     pub fn record(&self, sink_input: &SinkInput) -> Result<SinkInputStream, PulseClientError> {
         let props = self.props.clone();
 
-        let stream = SinkInputStream::new(
-            self.context.clone(),
-            self.event_sender.clone(),
-            props,
-            &self.spec,
-        );
+        let id = self.next_stream_id.fetch_add(1);
+
+        // Record at the native rate so the server doesn't resample, we do that ourselves.
+        // Converting to floats is lossless, so that part is still left to the server.
+        let native_spec = Spec {
+            rate: sink_input.rate,
+            ..self.spec
+        };
+
+        let spec = if native_spec.is_valid() && native_spec.rate >= MIN_INPUT_RATE {
+            native_spec
+        } else {
+            self.spec
+        };
+
+        let stream =
+            SinkInputStream::new(id, self.context.clone(), self.audio.clone(), props, &spec);
         stream.connect_to_sink_input(sink_input)?;
         stream.set_event_callbacks();
 
         Ok(stream)
     }
+
+    /// Creates a lightweight stream that only reports the peak level of a sink input
+    pub fn monitor_peaks(&self, sink_input: &SinkInput) -> Result<PeakStream, PulseClientError> {
+        let mut props = self.props.clone();
+
+        let spec = Spec {
+            format: Format::F32le,
+            channels: 1,
+            rate: PEAK_RATE,
+        };
+
+        let stream = {
+            let mut context = self.context.lock();
+
+            let stream = Stream::new_with_proplist(
+                &mut context,
+                "pulseshitter-peak",
+                &spec,
+                None,
+                &mut props,
+            )
+            .ok_or(PulseClientError::Fatal(
+                "Failed to create peak stream".to_string(),
+            ))?;
+
+            Arc::new(Mutex::new(stream))
+        };
+
+        let peak_stream = PeakStream {
+            sink: sink_input.sink,
+            stream,
+            peak: Arc::new(AtomicCell::new((0., Instant::now()))),
+        };
+
+        peak_stream.connect(sink_input)?;
+        Ok(peak_stream)
+    }
 }
 
 impl Drop for PulseClient {
@@ -236,13 +321,14 @@ pub enum PulseClientError {
 
 pub enum PulseClientEvent {
     SinkInput { index: u32, operation: Operation },
-    Audio(Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
 pub struct SinkInput {
     pub(super) name: String,
     pub(super) index: u32,
+    /// The native sample rate of the sink input
+    pub(super) rate: u32,
     pub(super) sink: u32,
     pub(super) volume: f32,
     pub(super) props: Proplist,
@@ -251,17 +337,20 @@ pub struct SinkInput {
 /// Represents a stream of audio from a sink input
 #[derive(Clone)]
 pub struct SinkInputStream {
+    id: u32,
+    rate: u32,
     context: Arc<Mutex<Context>>,
     stream: Arc<Mutex<Stream>>,
 
     status: Arc<RwLock<SinkInputStreamStatus>>,
-    event_sender: Sender<PulseClientEvent>,
+    audio: AudioQueue,
 }
 
 impl SinkInputStream {
     fn new(
+        id: u32,
         context: Arc<Mutex<Context>>,
-        event_sender: Sender<PulseClientEvent>,
+        audio: AudioQueue,
         mut props: Proplist,
         spec: &Spec,
     ) -> Self {
@@ -281,9 +370,11 @@ impl SinkInputStream {
         };
 
         Self {
+            id,
+            rate: spec.rate,
             context,
             stream,
-            event_sender,
+            audio,
             status: Default::default(),
         }
     }
@@ -322,8 +413,9 @@ impl SinkInputStream {
         })));
 
         locked_stream.set_read_callback(Some(Box::new({
+            let id = self.id;
             let stream = self.stream.clone();
-            let sender = self.event_sender.clone();
+            let audio = self.audio.clone();
 
             move |_| {
                 let mut stream = stream.lock();
@@ -333,9 +425,13 @@ impl SinkInputStream {
                         PeekResult::Empty => {}
                         PeekResult::Hole(_) => stream.discard().expect("Discards if hole"),
                         PeekResult::Data(data) => {
-                            sender
-                                .send(PulseClientEvent::Audio(data.to_vec()))
-                                .expect("Sends audio");
+                            let mut buffer = audio.take_buffer();
+                            buffer.extend_from_slice(data);
+
+                            audio.push(AudioChunk {
+                                stream: id,
+                                data: buffer,
+                            });
 
                             //producer.lock().push_slice(data);
                             stream.discard().expect("Discards after data");
@@ -378,11 +474,11 @@ impl SinkInputStream {
             .connect_record(
                 Some(sink_input.sink.to_string().as_str()),
                 Some(&BufferAttr {
-                    maxlength: std::u32::MAX,
+                    maxlength: u32::MAX,
                     tlength: 0,
                     prebuf: 0,
                     minreq: 0,
-                    fragsize: (BUFFER_SIZE / 2) as u32,
+                    fragsize: self.fragment_size(),
                 }),
                 StreamFlagSet::DONT_MOVE,
             )
@@ -391,6 +487,22 @@ impl SinkInputStream {
         Ok(())
     }
 
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Half the latency, in bytes at the native rate of the stream
+    fn fragment_size(&self) -> u32 {
+        let frame = SAMPLE_IN_BYTES * 2;
+        let frames = (LATENCY_SIZE / 2 / frame) * self.rate as usize / SAMPLE_RATE;
+
+        (frames * frame) as u32
+    }
+
     pub fn status(&self) -> SinkInputStreamStatus {
         self.status.read().clone()
     }
@@ -413,6 +525,98 @@ impl Drop for SinkInputStream {
     }
 }
 
+/// Reports how loud a sink input is, without recording its audio
+pub struct PeakStream {
+    /// The sink the sink input was playing on when the stream was connected
+    sink: u32,
+    stream: Arc<Mutex<Stream>>,
+
+    /// The latest peak and when it arrived
+    peak: Arc<AtomicCell<(f32, Instant)>>,
+}
+
+impl PeakStream {
+    fn connect(&self, sink_input: &SinkInput) -> Result<(), PulseClientError> {
+        let mut stream = self.stream.lock();
+
+        stream.set_read_callback(Some(Box::new({
+            let stream = self.stream.clone();
+            let peak = self.peak.clone();
+
+            move |_| {
+                let mut stream = stream.lock();
+
+                match stream.peek() {
+                    Ok(PeekResult::Data(data)) => {
+                        // Only the latest peak matters, older ones are already outdated
+                        if let Some(bytes) = data.rchunks_exact(SAMPLE_IN_BYTES).next() {
+                            let value = f32::from_le_bytes(bytes.try_into().unwrap());
+                            peak.store((value.abs(), Instant::now()));
+                        }
+
+                        stream.discard().ok();
+                    }
+                    Ok(PeekResult::Hole(_)) => {
+                        stream.discard().ok();
+                    }
+                    Ok(PeekResult::Empty) | Err(_) => {}
+                }
+            }
+        })));
+
+        stream
+            .set_monitor_stream(sink_input.index)
+            .map_err(|_| PulseClientError::Fatal("Failed to set monitor stream".to_string()))?;
+
+        stream
+            .connect_record(
+                Some(sink_input.sink.to_string().as_str()),
+                Some(&BufferAttr {
+                    maxlength: u32::MAX,
+                    tlength: 0,
+                    prebuf: 0,
+                    minreq: 0,
+                    fragsize: SAMPLE_IN_BYTES as u32,
+                }),
+                StreamFlagSet::PEAK_DETECT
+                    | StreamFlagSet::ADJUST_LATENCY
+                    | StreamFlagSet::DONT_MOVE
+                    | StreamFlagSet::DONT_INHIBIT_AUTO_SUSPEND,
+            )
+            .map_err(|_| PulseClientError::Fatal("Failed to connect peak stream".to_string()))
+    }
+
+    /// The sink this stream is recording from
+    pub fn sink(&self) -> u32 {
+        self.sink
+    }
+
+    /// The latest peak, from 0 to 1
+    pub fn peak(&self) -> f32 {
+        let (peak, time) = self.peak.load();
+
+        if time.elapsed() < PEAK_LIFETIME {
+            peak
+        } else {
+            0.
+        }
+    }
+}
+
+impl Drop for PeakStream {
+    fn drop(&mut self) {
+        let mut stream = self.stream.lock();
+
+        stream.set_read_callback(None);
+
+        if let StreamState::Ready = stream.get_state() {
+            stream.disconnect().unwrap_or_else(|e| {
+                eprintln!("Failed to disconnect peak stream: {}", e);
+            })
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub enum SinkInputStreamStatus {
     #[default]
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crossbeam::{
+    atomic::AtomicCell,
+    channel::{bounded, Receiver, Sender, TrySendError},
+};
+
+use super::pool::BufferPool;
+
+/// A chunk of audio recorded from a stream
+pub struct AudioChunk {
+    pub stream: u32,
+    pub data: Vec<u8>,
+}
+
+/// A bounded queue of audio chunks that drops the oldest chunk when it is full.
+/// This way a stall shows up as a glitch, instead of as ever-growing latency.
+#[derive(Clone)]
+pub struct AudioQueue {
+    sender: Sender<AudioChunk>,
+    receiver: Receiver<AudioChunk>,
+
+    pool: Arc<BufferPool>,
+    dropped: Arc<AtomicCell<u64>>,
+}
+
+impl AudioQueue {
+    pub fn new(length: usize, pool: Arc<BufferPool>) -> Self {
+        let (sender, receiver) = bounded(length);
+
+        Self {
+            sender,
+            receiver,
+            pool,
+            dropped: Default::default(),
+        }
+    }
+
+    pub fn push(&self, chunk: AudioChunk) {
+        let chunk = match self.sender.try_send(chunk) {
+            Ok(_) => return,
+            Err(TrySendError::Full(chunk)) => chunk,
+            Err(TrySendError::Disconnected(chunk)) => return self.pool.recycle(chunk.data),
+        };
+
+        if let Ok(oldest) = self.receiver.try_recv() {
+            self.drop_chunk(oldest);
+        }
+
+        // The consumer may have taken the free slot in the meantime, but it can't hurt to check
+        if let Err(error) = self.sender.try_send(chunk) {
+            self.drop_chunk(error.into_inner());
+        }
+    }
+
+    pub fn receiver(&self) -> &Receiver<AudioChunk> {
+        &self.receiver
+    }
+
+    /// Returns a buffer to the pool once the audio in it has been handled
+    pub fn recycle(&self, chunk: AudioChunk) {
+        self.pool.recycle(chunk.data)
+    }
+
+    /// Takes an empty buffer to record into
+    pub fn take_buffer(&self) -> Vec<u8> {
+        self.pool.take()
+    }
+
+    /// How many chunks have been dropped since the start
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load()
+    }
+
+    fn drop_chunk(&self, chunk: AudioChunk) {
+        self.dropped.fetch_add(1);
+        self.recycle(chunk);
+    }
+}
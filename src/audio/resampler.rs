@@ -0,0 +1,233 @@
+use std::f64::consts::PI;
+
+use crate::state::ResamplerQuality;
+
+use super::{Sample, LATENCY_SIZE, SAMPLE_IN_BYTES, SAMPLE_RATE};
+
+/// The lowest rate streams are recorded at, anything lower is left to the server to resample
+pub const MIN_INPUT_RATE: u32 = 8000;
+
+/// The most frames a chunk of recorded audio holds, which is what the pooled buffers fit
+const MAX_CHUNK_FRAMES: usize = LATENCY_SIZE / (SAMPLE_IN_BYTES * 2);
+
+/// The most samples a chunk can turn into, which is when it is upsampled from [MIN_INPUT_RATE]
+pub const MAX_OUTPUT_LENGTH: usize =
+    (MAX_CHUNK_FRAMES * SAMPLE_RATE / MIN_INPUT_RATE as usize + 1) * 2;
+
+/// Converts stereo audio from a source's native rate to [SAMPLE_RATE] using windowed sinc interpolation
+pub struct Resampler {
+    /// How far to advance in the input for every output frame
+    step: f64,
+    /// Half the amount of taps of the filter
+    half_taps: usize,
+    phases: usize,
+
+    /// The filter, `taps` coefficients for each of `phases + 1` fractional offsets
+    table: Vec<f32>,
+
+    /// Input frames that are still needed, interleaved
+    buffer: Vec<Sample>,
+    /// Where the next output frame is in the buffer, in frames
+    position: f64,
+
+    output: Vec<Sample>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, quality: ResamplerQuality) -> Self {
+        let (half_taps, phases, bandwidth) = match quality {
+            ResamplerQuality::Low => (8, 128, 0.9),
+            ResamplerQuality::Medium => (16, 256, 0.94),
+            ResamplerQuality::High => (32, 512, 0.97),
+        };
+
+        let step = input_rate as f64 / SAMPLE_RATE as f64;
+
+        // When downsampling, the cutoff has to be lowered to the new nyquist frequency
+        let cutoff = bandwidth * (1. / step).min(1.);
+
+        let taps = half_taps * 2;
+        let mut table = Vec::with_capacity(taps * (phases + 1));
+
+        for phase in 0..=phases {
+            let offset = phase as f64 / phases as f64;
+
+            for tap in 0..taps {
+                let distance = tap as f64 + 1. - half_taps as f64 - offset;
+                table.push(windowed_sinc(distance, cutoff, half_taps as f64) as f32);
+            }
+        }
+
+        // Room for the frames the filter looks back at on top of a whole chunk, so processing doesn't allocate
+        let mut buffer = Vec::with_capacity((MAX_CHUNK_FRAMES + taps + 1) * 2);
+        let output_frames = (MAX_CHUNK_FRAMES as f64 / step).ceil() as usize + 1;
+
+        // Silence before the first frame, so the filter has something to look back at
+        buffer.resize((half_taps - 1) * 2, 0.);
+
+        Self {
+            step,
+            half_taps,
+            phases,
+            table,
+            buffer,
+            position: (half_taps - 1) as f64,
+            output: Vec::with_capacity(output_frames * 2),
+        }
+    }
+
+    /// Whether the input is already at [SAMPLE_RATE]
+    pub fn is_passthrough(&self) -> bool {
+        self.step == 1.
+    }
+
+    /// Resamples the samples in place
+    pub fn process(&mut self, samples: &mut Vec<Sample>) {
+        if self.is_passthrough() {
+            return;
+        }
+
+        self.buffer.extend_from_slice(samples);
+        self.output.clear();
+
+        let taps = self.half_taps * 2;
+        let frames = self.buffer.len() / 2;
+
+        loop {
+            let index = self.position as usize;
+
+            if index + self.half_taps >= frames {
+                break;
+            }
+
+            let fraction = (self.position - index as f64) * self.phases as f64;
+            let phase = fraction as usize;
+            let blend = (fraction - phase as f64) as f32;
+
+            let coefficients = &self.table[phase * taps..(phase + 2) * taps];
+            let (current, next) = coefficients.split_at(taps);
+
+            let start = (index + 1 - self.half_taps) * 2;
+            let window = &self.buffer[start..start + taps * 2];
+
+            let (mut left, mut right) = (0., 0.);
+
+            for ((frame, a), b) in window.chunks_exact(2).zip(current).zip(next) {
+                let weight = a + (b - a) * blend;
+
+                left += frame[0] * weight;
+                right += frame[1] * weight;
+            }
+
+            self.output.push(left);
+            self.output.push(right);
+            self.position += self.step;
+        }
+
+        // Forget the frames the filter won't look at anymore
+        let consumed = (self.position as usize + 1).saturating_sub(self.half_taps);
+        self.buffer.drain(..consumed * 2);
+        self.position -= consumed as f64;
+
+        samples.clear();
+        samples.extend_from_slice(&self.output);
+    }
+}
+
+/// A lowpass filter kernel at the given distance from its center, using a Blackman window
+pub(super) fn windowed_sinc(distance: f64, cutoff: f64, half_width: f64) -> f64 {
+    if distance.abs() >= half_width {
+        return 0.;
+    }
+
+    let x = PI * distance * cutoff;
+    let sinc = if x == 0. { 1. } else { x.sin() / x };
+
+    let window_position = (distance / half_width + 1.) / 2.;
+    let window =
+        0.42 - 0.5 * (2. * PI * window_position).cos() + 0.08 * (4. * PI * window_position).cos();
+
+    cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Interleaved stereo chunks of 10 ms at the rate, with a different constant in each channel
+    fn chunks(rate: u32, seconds: usize) -> impl Iterator<Item = Vec<Sample>> {
+        let frames = rate as usize / 100;
+        (0..seconds * 100).map(move |_| [0.5, -0.25].repeat(frames))
+    }
+
+    #[test]
+    fn passes_native_rate_through() {
+        let mut resampler = Resampler::new(SAMPLE_RATE as u32, ResamplerQuality::High);
+        assert!(resampler.is_passthrough());
+
+        let input: Vec<Sample> = (0..960).map(|i| (i as f32 * 0.01).sin()).collect();
+        let mut samples = input.clone();
+        resampler.process(&mut samples);
+
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn converts_length_by_the_rate_ratio() {
+        for quality in [
+            ResamplerQuality::Low,
+            ResamplerQuality::Medium,
+            ResamplerQuality::High,
+        ] {
+            let mut resampler = Resampler::new(44100, quality);
+            let mut output_frames = 0;
+
+            for mut chunk in chunks(44100, 2) {
+                resampler.process(&mut chunk);
+                output_frames += chunk.len() / 2;
+            }
+
+            // The filter holds back half its taps at the end
+            let expected = SAMPLE_RATE * 2;
+            assert!(
+                output_frames.abs_diff(expected) <= 64,
+                "expected about {expected} frames, got {output_frames}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_level_and_channels() {
+        let mut resampler = Resampler::new(44100, ResamplerQuality::Medium);
+        let mut output = vec![];
+
+        for mut chunk in chunks(44100, 1) {
+            resampler.process(&mut chunk);
+            output.extend(chunk);
+        }
+
+        // Past the start, where the filter still looks back at silence
+        for frame in output[1000..].chunks_exact(2) {
+            assert!((frame[0] - 0.5).abs() < 0.01, "left was {}", frame[0]);
+            assert!((frame[1] + 0.25).abs() < 0.01, "right was {}", frame[1]);
+        }
+    }
+
+    #[test]
+    fn full_chunks_fit_the_preallocated_buffers() {
+        for rate in [MIN_INPUT_RATE, 44100, 96000] {
+            let mut resampler = Resampler::new(rate, ResamplerQuality::High);
+            let capacities = (resampler.buffer.capacity(), resampler.output.capacity());
+
+            for _ in 0..10 {
+                let mut chunk = [0.5, -0.25].repeat(MAX_CHUNK_FRAMES);
+                resampler.process(&mut chunk);
+
+                assert!(chunk.len() <= MAX_OUTPUT_LENGTH);
+            }
+
+            let after = (resampler.buffer.capacity(), resampler.output.capacity());
+            assert_eq!(capacities, after, "buffers grew at {rate} Hz");
+        }
+    }
+}
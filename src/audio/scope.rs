@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use super::{Sample, SAMPLE_RATE};
+
+/// How many frames each point of the waveform covers
+const FRAMES_PER_ENVELOPE: usize = SAMPLE_RATE / 100;
+/// How many points of the waveform are kept, which is 3 seconds
+pub const ENVELOPE_COUNT: usize = 300;
+
+/// How many of the most recent frames are kept for the goniometer
+const GONIOMETER_FRAMES: usize = 1024;
+
+/// Keeps the recent waveform of the stream around to draw it
+pub struct Scope {
+    envelopes: VecDeque<Envelope>,
+    current: Envelope,
+    current_frames: usize,
+
+    frames: VecDeque<[Sample; 2]>,
+}
+
+/// The lowest and highest sample of each channel over a short period
+#[derive(Debug, Clone, Copy)]
+pub struct Envelope {
+    pub min: [Sample; 2],
+    pub max: [Sample; 2],
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self {
+            envelopes: VecDeque::from(vec![Envelope::SILENT; ENVELOPE_COUNT]),
+            current: Envelope::EMPTY,
+            current_frames: 0,
+            frames: VecDeque::from(vec![[0.; 2]; GONIOMETER_FRAMES]),
+        }
+    }
+
+    pub fn write(&mut self, samples: &[Sample]) {
+        for frame in samples.chunks_exact(2) {
+            self.push([frame[0], frame[1]]);
+        }
+    }
+
+    fn push(&mut self, frame: [Sample; 2]) {
+        for (channel, &sample) in frame.iter().enumerate() {
+            self.current.min[channel] = self.current.min[channel].min(sample);
+            self.current.max[channel] = self.current.max[channel].max(sample);
+        }
+
+        self.current_frames += 1;
+
+        if self.current_frames == FRAMES_PER_ENVELOPE {
+            self.envelopes.pop_front();
+            self.envelopes.push_back(self.current);
+
+            self.current = Envelope::EMPTY;
+            self.current_frames = 0;
+        }
+
+        self.frames.pop_front();
+        self.frames.push_back(frame);
+    }
+
+    /// Copies the waveform and the most recent frames, from oldest to newest, reusing their allocations
+    pub fn publish(&self, envelopes: &mut Vec<Envelope>, frames: &mut Vec<[Sample; 2]>) {
+        envelopes.clear();
+        envelopes.extend(&self.envelopes);
+
+        frames.clear();
+        frames.extend(&self.frames);
+    }
+}
+
+impl Envelope {
+    const EMPTY: Self = Self {
+        min: [Sample::INFINITY; 2],
+        max: [Sample::NEG_INFINITY; 2],
+    };
+
+    const SILENT: Self = Self {
+        min: [0.; 2],
+        max: [0.; 2],
+    };
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
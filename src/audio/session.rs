@@ -0,0 +1,172 @@
+use std::{
+    env,
+    fs::{self, File},
+    io,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use super::{analysis::SessionMeasurements, BufferStats, Source};
+
+/// Keeps track of what was streamed during the session, to summarize it when it ends
+pub struct Session {
+    started: SystemTime,
+
+    sources: Vec<SourceTime>,
+    /// The source being streamed right now and since when, as an index into `sources`
+    current: Option<(usize, Instant)>,
+}
+
+/// How long a source was streamed for
+struct SourceTime {
+    application: String,
+    name: String,
+    duration: Duration,
+}
+
+/// What is written to disk when a session ends
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    /// In seconds since the unix epoch
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub sources: Vec<SourceSummary>,
+
+    /// Unset when nothing loud enough was streamed to measure
+    pub integrated_loudness_lufs: Option<f32>,
+    pub max_true_peak_dbtp: Option<f32>,
+    pub clips: u64,
+
+    pub underruns: u64,
+    pub overruns: u64,
+    /// Chunks of audio that were dropped before reaching the buffer
+    pub dropped_chunks: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceSummary {
+    pub application: String,
+    pub name: String,
+    pub seconds: f64,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            started: SystemTime::now(),
+            sources: vec![],
+            current: None,
+        }
+    }
+
+    /// Starts counting time for the source, or stops counting when there is none
+    pub fn set_streaming(&mut self, source: Option<&Source>) {
+        let index = source.map(|source| {
+            let (application, name) = (source.application(), source.name());
+
+            match self
+                .sources
+                .iter()
+                .position(|s| s.application == application && s.name == name)
+            {
+                Some(index) => index,
+                None => {
+                    self.sources.push(SourceTime {
+                        application,
+                        name,
+                        duration: Duration::ZERO,
+                    });
+
+                    self.sources.len() - 1
+                }
+            }
+        });
+
+        if self.current.map(|(current, _)| current) == index {
+            return;
+        }
+
+        self.stop_counting();
+        self.current = index.map(|index| (index, Instant::now()));
+    }
+
+    fn stop_counting(&mut self) {
+        if let Some((index, since)) = self.current.take() {
+            self.sources[index].duration += since.elapsed();
+        }
+    }
+
+    /// Sums up the session so far
+    pub fn summarize(
+        &mut self,
+        measurements: SessionMeasurements,
+        buffer: BufferStats,
+    ) -> SessionSummary {
+        // Counts the time of the current source up to now, and keeps counting after
+        if let Some((index, _)) = self.current {
+            self.stop_counting();
+            self.current = Some((index, Instant::now()));
+        }
+
+        SessionSummary {
+            started_at: unix_seconds(self.started),
+            ended_at: unix_seconds(SystemTime::now()),
+            sources: self
+                .sources
+                .iter()
+                .map(|s| SourceSummary {
+                    application: s.application.clone(),
+                    name: s.name.clone(),
+                    seconds: s.duration.as_secs_f64(),
+                })
+                .collect(),
+            integrated_loudness_lufs: Some(measurements.integrated_loudness)
+                .filter(|l| l.is_finite()),
+            max_true_peak_dbtp: Some(measurements.max_true_peak).filter(|p| p.is_finite()),
+            clips: measurements.clips,
+            underruns: buffer.underruns,
+            overruns: buffer.overruns,
+            dropped_chunks: buffer.dropped,
+        }
+    }
+}
+
+impl SessionSummary {
+    fn directory() -> PathBuf {
+        let state_dir = env::var("XDG_STATE_HOME")
+            .or_else(|_| env::var("HOME").map(|path| path + "/.local/state"))
+            .unwrap_or_else(|_| ".".to_string());
+
+        PathBuf::from(state_dir).join("pulseshitter")
+    }
+
+    /// Whether anything was streamed at all, otherwise there is nothing worth saving
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Writes the summary to the state directory, returning where it ended up
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let directory = Self::directory();
+        fs::create_dir_all(&directory)?;
+
+        let path = directory.join(format!("session-{}.json", self.started_at));
+        serde_json::to_writer_pretty(File::create(&path)?, self)?;
+
+        Ok(path)
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,14 @@
 // TODO: Remove this once the dead code is used
 #![allow(unused)]
 
-use super::pulse::{PulseClient, SinkInput};
+use super::pulse::{PeakStream, PulseClient, SinkInput};
 use crossbeam::atomic::AtomicCell;
 use lazy_static::lazy_static;
 use libpulse_binding::context::subscribe::Operation;
 use parking_lot::{Mutex, RwLock};
 use regex::Regex;
 use std::{
+    collections::HashMap,
     fmt::Display,
     sync::Arc,
     time::{Duration, Instant},
@@ -39,6 +40,9 @@ pub struct SourceSelector {
     /// Not to be confused with current source which is what is currently being streamed.
     selected_source: Mutex<Option<Source>>,
     stored_sources: Mutex<Vec<Source>>,
+
+    /// Peak streams of every available source, by sink input index
+    peak_streams: Mutex<HashMap<u32, PeakStream>>,
 }
 
 impl SourceSelector {
@@ -50,12 +54,17 @@ impl SourceSelector {
             .map(|f| f.into())
             .collect();
 
-        Self {
+        let selector = Self {
             client,
-            stored_sources: sources.into(),
+            stored_sources: Default::default(),
             current_source: Default::default(),
             selected_source: Default::default(),
-        }
+            peak_streams: Default::default(),
+        };
+
+        selector.sync_peak_streams(&sources);
+        *selector.stored_sources.lock() = sources;
+        selector
     }
 
     pub fn sources(&self) -> Vec<Source> {
@@ -78,6 +87,15 @@ impl SourceSelector {
         self.selected_source.lock().clone()
     }
 
+    /// Returns the volume of the current source without cloning it
+    pub fn current_volume(&self) -> Option<f32> {
+        self.current_source
+            .lock()
+            .as_ref()
+            .filter(|s| s.available())
+            .map(|s| s.volume())
+    }
+
     pub(super) fn select(&self, source: Option<Source>) {
         match source {
             Some(x) => {
@@ -136,6 +154,45 @@ impl SourceSelector {
         }
 
         current_sources.retain(|s| !s.is_dead());
+        self.sync_peak_streams(&current_sources);
+    }
+
+    /// The latest peak of a source, from 0 to 1
+    pub fn peak(&self, source: &Source) -> f32 {
+        self.peak_streams
+            .lock()
+            .get(&source.index())
+            .map(|s| s.peak())
+            .unwrap_or_default()
+    }
+
+    /// Makes sure every available source has a peak stream, and nothing else does
+    fn sync_peak_streams(&self, sources: &[Source]) {
+        let mut peak_streams = self.peak_streams.lock();
+
+        let sink_inputs: Vec<_> = sources
+            .iter()
+            .filter(|s| s.available())
+            .map(|s| s.sink_input())
+            .collect();
+
+        // Peak streams don't follow sink inputs that move to another sink
+        peak_streams.retain(|index, stream| {
+            sink_inputs
+                .iter()
+                .any(|s| s.index == *index && s.sink == stream.sink())
+        });
+
+        for sink_input in sink_inputs {
+            if peak_streams.contains_key(&sink_input.index) {
+                continue;
+            }
+
+            // A missing meter isn't worth bothering anyone about
+            if let Ok(stream) = self.client.monitor_peaks(&sink_input) {
+                peak_streams.insert(sink_input.index, stream);
+            }
+        }
     }
 }
 
@@ -225,6 +282,10 @@ impl Source {
     pub fn name(&self) -> String {
         self.name.read().clone()
     }
+
+    pub fn application(&self) -> String {
+        self.application.clone()
+    }
 }
 
 impl SourceComparison {
@@ -0,0 +1,186 @@
+use crate::interface::TARGET_FPS;
+
+use super::{analysis::Window, fft::Fft, Sample, SAMPLE_RATE};
+
+const FFT_SIZE: usize = 4096;
+
+/// Amount of log-spaced bands the spectrum is divided into
+pub const BAND_COUNT: usize = 64;
+
+const MIN_FREQUENCY: f32 = 20.;
+const MAX_FREQUENCY: f32 = 20000.;
+
+/// The range of levels shown, below 0 dBFS
+const DB_RANGE: f32 = 90.;
+
+/// How much of the previous level is kept every tick while a band is falling
+const SMOOTHING: f32 = 0.85;
+
+/// How long a peak is held before it starts falling
+const PEAK_HOLD_TICKS: u32 = TARGET_FPS / 2;
+/// How fast peaks fall once they are no longer held, in dB per second
+const PEAK_DECAY_DB: f32 = 30.;
+
+/// Analyzes the frequency content of the stream
+pub struct Spectrum {
+    window: Window,
+    state: State,
+}
+
+/// Everything needed to turn the window into bands, kept around to avoid allocating
+struct State {
+    fft: Fft,
+    /// Hann window coefficients
+    coefficients: Vec<f32>,
+    real: Vec<f32>,
+    imag: Vec<f32>,
+
+    /// The FFT bins each band covers
+    ranges: Vec<(usize, usize)>,
+    bands: Vec<Band>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Band {
+    /// The smoothed level, in dBFS
+    pub level: f32,
+    /// The highest recent level, in dBFS
+    pub peak: f32,
+    peak_age: u32,
+}
+
+impl Spectrum {
+    pub fn new() -> Self {
+        Self {
+            window: Window::new(FFT_SIZE),
+            state: State::new(),
+        }
+    }
+
+    /// Adds interleaved stereo samples, mixed down to mono
+    pub fn write(&mut self, samples: &[Sample]) {
+        for frame in samples.chunks_exact(2) {
+            self.window.push((frame[0] + frame[1]) / 2.);
+        }
+    }
+
+    pub fn process(&mut self) {
+        let Self { window, state } = self;
+        let (older, newer) = window.samples.split_at(window.position);
+
+        for (i, sample) in newer.iter().chain(older).enumerate() {
+            state.real[i] = sample * state.coefficients[i];
+            state.imag[i] = 0.;
+        }
+
+        state.process();
+    }
+
+    /// Copies the bands into `bands`, reusing its allocation
+    pub fn publish(&self, bands: &mut Vec<Band>) {
+        bands.clone_from(&self.state.bands);
+    }
+}
+
+impl State {
+    fn new() -> Self {
+        let coefficients = (0..FFT_SIZE)
+            .map(|i| {
+                let phase = i as f32 / (FFT_SIZE - 1) as f32;
+                0.5 - 0.5 * (2. * std::f32::consts::PI * phase).cos()
+            })
+            .collect();
+
+        let bin_width = SAMPLE_RATE as f32 / FFT_SIZE as f32;
+        let ratio = MAX_FREQUENCY / MIN_FREQUENCY;
+
+        let ranges = (0..BAND_COUNT)
+            .map(|band| {
+                let low = MIN_FREQUENCY * ratio.powf(band as f32 / BAND_COUNT as f32);
+                let high = MIN_FREQUENCY * ratio.powf((band + 1) as f32 / BAND_COUNT as f32);
+
+                // Low bands are narrower than a bin, so they get at least one
+                let start = (low / bin_width).round() as usize;
+                let end = ((high / bin_width).round() as usize).max(start + 1);
+
+                (start.min(FFT_SIZE / 2 - 1), end.min(FFT_SIZE / 2))
+            })
+            .collect();
+
+        let silent = Band {
+            level: -DB_RANGE,
+            peak: -DB_RANGE,
+            peak_age: 0,
+        };
+
+        Self {
+            fft: Fft::new(FFT_SIZE),
+            coefficients,
+            real: vec![0.; FFT_SIZE],
+            imag: vec![0.; FFT_SIZE],
+            ranges,
+            bands: vec![silent; BAND_COUNT],
+        }
+    }
+
+    fn process(&mut self) {
+        let Self {
+            fft,
+            real,
+            imag,
+            ranges,
+            bands,
+            ..
+        } = self;
+
+        fft.process(real, imag);
+
+        // Scales a full scale sine to 0 dBFS, the Hann window halves the amplitude
+        let scale = 4. / FFT_SIZE as f32;
+        let peak_decay = PEAK_DECAY_DB / TARGET_FPS as f32;
+
+        for (band, &(start, end)) in bands.iter_mut().zip(ranges.iter()) {
+            let magnitude = (start..end)
+                .map(|bin| (real[bin] * real[bin] + imag[bin] * imag[bin]).sqrt())
+                .fold(0., f32::max);
+
+            let level = (20. * (magnitude * scale).max(f32::EPSILON).log10()).max(-DB_RANGE);
+
+            band.level = if level > band.level {
+                level
+            } else {
+                band.level * SMOOTHING + level * (1. - SMOOTHING)
+            };
+
+            if band.level >= band.peak {
+                band.peak = band.level;
+                band.peak_age = 0;
+            } else if band.peak_age < PEAK_HOLD_TICKS {
+                band.peak_age += 1;
+            } else {
+                band.peak = (band.peak - peak_decay).max(band.level);
+            }
+        }
+    }
+}
+
+impl Band {
+    /// The level as a value from 0 to 1
+    pub fn level_ranged(&self) -> f32 {
+        ranged(self.level)
+    }
+
+    pub fn peak_ranged(&self) -> f32 {
+        ranged(self.peak)
+    }
+}
+
+fn ranged(db: f32) -> f32 {
+    ((DB_RANGE + db) / DB_RANGE).clamp(0., 1.)
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,33 @@
-use std::{
-    io::{Read, Seek},
-    sync::Arc,
-    thread,
-};
+use std::{io::Read, iter, mem, sync::Arc, thread, time::Duration};
 
+use crossbeam::{atomic::AtomicCell, select};
+use libpulse_binding::context::subscribe::Operation;
 use parking_lot::Mutex;
-use ringbuf::HeapRb;
-use songbird::input::{reader::MediaSource, Codec, Container, Input, Reader};
+use ringbuf::{HeapProducer, HeapRb};
+use songbird::input::{codec::OpusDecoderState, Codec, Container, Input, Reader};
+
+use crate::state::{
+    AgcConfig, AudioConfig, DuckingConfig, EffectConfig, EncoderConfig, ResamplerQuality,
+};
 
 use super::{
-    analysis::{raw_samples_from_bytes, spawn_analysis_thread, StereoMeter},
-    pulse::{PulseClient, PulseClientError, PulseClientEvent, SinkInputStream},
+    agc::{Agc, AgcStats},
+    analysis::{read_samples, write_samples, Analysis, AnalysisInput},
+    ducking::Ducker,
+    duration_in_bytes,
+    effects::{EffectChain, EffectContext, EffectUpdate},
+    encoder::{EncodedStream, OpusEncoder},
+    history::HistoryPoint,
+    jitter::{BufferHealth, BufferStats, JitterBuffer},
+    loudness::Loudness,
+    pulse::{PulseClient, PulseClientError, PulseClientEvent},
+    resampler::{Resampler, MAX_OUTPUT_LENGTH},
+    scope::Envelope,
+    session::{Session, SessionSummary},
     source::{Source, SourceSelector},
-    AudioConsumer, AudioProducer, BUFFER_SIZE, SAMPLE_IN_BYTES,
+    spectrum::Band,
+    transition::{ResamplerUpdate, Transition, OUTGOING_GRACE_PERIOD},
+    AudioConsumer, AudioProducer, Sample, BUFFER_SIZE, MAX_DELAY_SIZE, SAMPLE_IN_BYTES,
 };
 
 /// Manages all audio related stuff
@@ -20,12 +35,27 @@ pub struct AudioSystem {
     client: Arc<PulseClient>,
 
     selector: Arc<SourceSelector>,
-    stream: Arc<Mutex<Option<SinkInputStream>>>,
+    transition: Arc<Mutex<Transition>>,
 
     producer: AudioProducer,
     consumer: AudioConsumer,
 
-    meter: Arc<StereoMeter>,
+    analysis: Arc<Analysis>,
+    config: Arc<Mutex<AudioConfig>>,
+
+    ducker: Mutex<Ducker>,
+    effects: Mutex<EffectChain>,
+    /// The configs the effect chain was built from, which the audio thread never touches
+    effect_configs: Mutex<Vec<EffectConfig>>,
+    agc: Mutex<Agc>,
+    is_ducked: AtomicCell<bool>,
+    is_paused: AtomicCell<bool>,
+    session: Mutex<Session>,
+
+    /// The delay that has been applied to the buffer so far, in bytes
+    applied_delay: AtomicCell<usize>,
+
+    buffer_health: Arc<BufferHealth>,
 }
 
 impl AudioSystem {
@@ -36,28 +66,110 @@ impl AudioSystem {
         let selector = Arc::new(SourceSelector::new(client.clone()));
 
         let (audio_producer, audio_consumer) = HeapRb::new(BUFFER_SIZE).split();
+        let (analysis, analysis_input) = Analysis::spawn();
 
         let audio = Arc::new(Self {
             client,
             selector,
-            stream: Default::default(),
-            meter: StereoMeter::new().into(),
+            transition: Default::default(),
+            config: Default::default(),
+            ducker: Default::default(),
+            effects: Default::default(),
+            effect_configs: Default::default(),
+            agc: Default::default(),
+            is_ducked: Default::default(),
+            is_paused: Default::default(),
+            session: Default::default(),
+            applied_delay: Default::default(),
+            buffer_health: Default::default(),
+            analysis,
             producer: Mutex::new(audio_producer).into(),
             consumer: Mutex::new(audio_consumer).into(),
         });
 
-        spawn_analysis_thread(audio.meter.clone());
-        spawn_event_thread(audio.clone());
+        spawn_event_thread(audio.clone(), analysis_input);
         Ok(audio)
     }
 
     pub fn select(&self, source: Option<Source>) {
         self.selector.select(source);
         self.refresh_stream();
+        self.update_session();
     }
 
     pub fn stream(&self) -> AudioStream {
-        AudioStream(self.consumer.clone())
+        AudioStream {
+            consumer: self.consumer.clone(),
+            config: self.config.clone(),
+            jitter: JitterBuffer::new(self.buffer_health.clone()),
+        }
+    }
+
+    pub fn buffer_stats(&self) -> BufferStats {
+        BufferStats {
+            dropped: self.client.dropped_chunks(),
+            ..self.buffer_health.stats()
+        }
+    }
+
+    pub fn set_config(&self, config: AudioConfig) {
+        self.analysis.set_ballistics(config.meter_ballistics);
+        self.update_effects(&config.effects);
+
+        let quality = config.resampler_quality;
+        let previous = mem::replace(&mut *self.config.lock(), config);
+
+        if previous.resampler_quality != quality {
+            self.update_resamplers(quality);
+        }
+    }
+
+    /// Prepares changes to the effect chain here, so the audio thread only has to swap them in
+    fn update_effects(&self, configs: &[EffectConfig]) {
+        let mut effect_configs = self.effect_configs.lock();
+
+        if let Some(update) = EffectUpdate::new(&effect_configs, configs) {
+            let replaced = self.effects.lock().apply(update);
+
+            // Unloading effects can take a while, so it happens after the chain is unlocked
+            drop(replaced);
+        }
+
+        *effect_configs = configs.to_vec();
+    }
+
+    /// Builds resamplers at the new quality here, so the audio thread only has to swap them in
+    fn update_resamplers(&self, quality: ResamplerQuality) {
+        let streams = self.transition.lock().streams();
+        let update = ResamplerUpdate::new(&streams, quality);
+
+        let replaced = self.transition.lock().apply(update);
+        drop(replaced);
+    }
+
+    /// Lowers the stream while someone in the voice channel is talking
+    pub fn set_ducked(&self, is_ducked: bool) {
+        self.is_ducked.store(is_ducked);
+    }
+
+    /// Stops sending audio to the buffer while keeping the source selected
+    pub fn set_paused(&self, is_paused: bool) {
+        let was_paused = self.is_paused.swap(is_paused);
+
+        // Whatever was left in the buffer is stale by now
+        if was_paused && !is_paused {
+            self.buffer_health.request_reset();
+        }
+
+        self.update_session();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused.load()
+    }
+
+    pub fn agc_stats(&self) -> AgcStats {
+        self.agc.lock().stats()
     }
 
     pub fn sources(&self) -> Vec<Source> {
@@ -72,65 +184,248 @@ impl AudioSystem {
         self.selector.selected_source()
     }
 
-    pub fn meter_value_ranged(&self) -> (f32, f32) {
-        self.meter.value_ranged()
+    /// The latest peak of a source, from 0 to 1
+    pub fn source_peak(&self, source: &Source) -> f32 {
+        self.selector.peak(source)
+    }
+
+    /// The level of each channel, in dBFS
+    pub fn meter_dbfs(&self) -> (f32, f32) {
+        self.analysis.meter().dbfs
+    }
+
+    /// The held true peak of each channel, in dBTP
+    pub fn peak_hold_db(&self) -> (f32, f32) {
+        self.analysis.meter().peak_hold_db
+    }
+
+    /// The phase correlation between the channels, from -1 to 1
+    pub fn correlation(&self) -> f32 {
+        self.analysis.meter().correlation
+    }
+
+    /// How much louder the right channel is than the left one, in dB
+    pub fn balance(&self) -> f32 {
+        self.analysis.meter().balance
+    }
+
+    /// Whether each channel has clipped since the clip indicators were last reset
+    pub fn clipped(&self) -> (bool, bool) {
+        self.analysis.meter().clipped
+    }
+
+    pub fn reset_clips(&self) {
+        self.analysis.reset_clips();
+    }
+
+    pub fn loudness(&self) -> Loudness {
+        self.analysis.loudness()
+    }
+
+    pub fn reset_loudness(&self) {
+        self.analysis.reset_loudness();
+    }
+
+    pub fn spectrum(&self) -> Vec<Band> {
+        self.analysis.spectrum()
+    }
+
+    pub fn waveform(&self) -> Vec<Envelope> {
+        self.analysis.waveform()
+    }
+
+    pub fn recent_frames(&self) -> Vec<[Sample; 2]> {
+        self.analysis.frames()
+    }
+
+    pub fn loudness_history(&self) -> Vec<HistoryPoint> {
+        self.analysis.history()
+    }
+
+    /// Sums up how the session went so far
+    pub fn session_summary(&self) -> SessionSummary {
+        let measurements = self.analysis.session();
+        let buffer = self.buffer_stats();
+
+        self.session.lock().summarize(measurements, buffer)
+    }
+
+    /// Keeps track of which source is actually being streamed
+    fn update_session(&self) {
+        let source = self
+            .selector
+            .current_source()
+            .filter(|_| !self.is_paused.load());
+
+        self.session.lock().set_streaming(source.as_ref());
     }
 
     fn refresh_stream(&self) {
+        let config = self.config.lock().clone();
         let current_source = self.selector.current_source();
 
-        if let Some(source) = current_source {
+        let stream = current_source.map(|source| {
             let stream = self
                 .client
                 .record(&source.sink_input())
                 .expect("Creates recording stream");
 
-            *self.stream.lock() = Some(stream);
-        } else {
-            *self.stream.lock() = None;
+            let resampler = Resampler::new(stream.rate(), config.resampler_quality);
+            (stream, resampler)
+        });
+
+        self.transition.lock().switch(stream, &config);
+    }
+
+    fn handle_sink_input_event(&self, index: u32, operation: Operation) {
+        let old_id = self
+            .selector
+            .current_source()
+            .map(|s| s.index())
+            .unwrap_or_default();
+
+        self.selector.handle_sink_input_event(index, operation);
+
+        let new_id = self
+            .selector
+            .current_source()
+            .map(|s| s.index())
+            .unwrap_or_default();
+
+        if new_id != old_id {
+            self.refresh_stream();
+        }
+    }
+
+    /// Grows the delay by pushing silence ahead of the audio.
+    /// Shrinking it is handled by the [JitterBuffer] skipping what it doesn't need.
+    fn apply_delay(&self, producer: &mut HeapProducer<u8>, delay: Duration) {
+        let delay = duration_in_bytes(delay).min(MAX_DELAY_SIZE);
+        let applied_delay = self.applied_delay.swap(delay);
+
+        if delay > applied_delay {
+            producer.push_iter(&mut iter::repeat_n(0, delay - applied_delay));
         }
     }
+
+    fn handle_audio(
+        &self,
+        producer: &mut HeapProducer<u8>,
+        analysis: &mut AnalysisInput,
+        buffers: &mut ProcessingBuffers,
+        stream: u32,
+        data: &[u8],
+    ) {
+        let current_source_volume = self.selector.current_volume().unwrap_or(1.);
+
+        let samples = &mut buffers.samples;
+        read_samples(data, samples);
+
+        let config = ProcessingConfig::new(&self.config.lock());
+
+        // Transitions keep going while paused, so a switch isn't left hanging
+        if !self
+            .transition
+            .lock()
+            .process(stream, samples, config.fade_in)
+            || self.is_paused.load()
+        {
+            return;
+        }
+
+        let mut agc = self.agc.lock();
+        agc.apply(samples, &config.agc);
+
+        let is_ducked = self.is_ducked.load();
+
+        self.ducker
+            .lock()
+            .apply(samples, is_ducked, &config.ducking);
+
+        let context = EffectContext {
+            source_volume: current_source_volume,
+        };
+
+        self.effects.lock().process(samples, &context);
+
+        // Ducking would make the gain creep up to make up for it
+        if !is_ducked || !config.ducking.enabled {
+            agc.observe(samples, &config.agc);
+        }
+
+        write_samples(samples, &mut buffers.bytes);
+
+        self.apply_delay(producer, config.delay);
+        producer.push_slice(&buffers.bytes);
+        analysis.write(samples);
+    }
 }
 
-fn spawn_event_thread(audio: Arc<AudioSystem>) {
+/// The settings the audio thread uses, copied out so the config isn't locked while processing
+struct ProcessingConfig {
+    fade_in: Duration,
+    delay: Duration,
+    ducking: DuckingConfig,
+    agc: AgcConfig,
+}
+
+impl ProcessingConfig {
+    fn new(config: &AudioConfig) -> Self {
+        Self {
+            fade_in: config.fade_in(),
+            delay: config.delay(),
+            ducking: config.ducking.clone(),
+            agc: config.agc.clone(),
+        }
+    }
+}
+
+/// Buffers reused by the audio thread, so processing doesn't allocate
+struct ProcessingBuffers {
+    samples: Vec<Sample>,
+    bytes: Vec<u8>,
+}
+
+impl ProcessingBuffers {
+    fn new() -> Self {
+        Self {
+            // Resampling can make a chunk longer than it was recorded
+            samples: Vec::with_capacity(MAX_OUTPUT_LENGTH),
+            bytes: Vec::with_capacity(MAX_OUTPUT_LENGTH * SAMPLE_IN_BYTES),
+        }
+    }
+}
+
+fn spawn_event_thread(audio: Arc<AudioSystem>, mut analysis: AnalysisInput) {
     let run = move || {
         let events = audio.client.events.clone();
+        let chunks = audio.client.audio.receiver().clone();
+
         let mut producer = audio.producer.lock();
+        let mut buffers = ProcessingBuffers::new();
 
         loop {
-            match events.recv().unwrap() {
-                PulseClientEvent::SinkInput { index, operation } => {
-                    let old_id = audio
-                        .selector
-                        .current_source()
-                        .map(|s| s.index())
-                        .unwrap_or_default();
-
-                    audio.selector.handle_sink_input_event(index, operation);
-
-                    let new_id = audio
-                        .selector
-                        .current_source()
-                        .map(|s| s.index())
-                        .unwrap_or_default();
-
-                    if new_id != old_id {
-                        audio.refresh_stream();
+            {
+                let config = audio.config.lock();
+                audio.transition.lock().drop_stale(&config);
+            }
+
+            select! {
+                recv(events) -> event => match event {
+                    Ok(PulseClientEvent::SinkInput { index, operation }) => {
+                        audio.handle_sink_input_event(index, operation)
                     }
-                }
-                PulseClientEvent::Audio(data) => {
-                    let current_source_volume = audio
-                        .selector
-                        .current_source()
-                        .map(|s| s.volume())
-                        .unwrap_or(1.);
-
-                    let normalized_bytes = normalize_volume(&data, current_source_volume);
-
-                    producer.push_slice(&normalized_bytes);
-                    audio.meter.write(&normalized_bytes);
-                }
-            };
+                    Err(_) => break,
+                },
+                recv(chunks) -> chunk => match chunk {
+                    Ok(chunk) => {
+                        audio.handle_audio(&mut producer, &mut analysis, &mut buffers, chunk.stream, &chunk.data);
+                        audio.client.audio.recycle(chunk);
+                    }
+                    Err(_) => break,
+                },
+                default(OUTGOING_GRACE_PERIOD) => {},
+            }
         }
     };
 
@@ -141,62 +436,53 @@ fn spawn_event_thread(audio: Arc<AudioSystem>) {
 }
 
 #[derive(Clone)]
-pub struct AudioStream(AudioConsumer);
+pub struct AudioStream {
+    consumer: AudioConsumer,
+    config: Arc<Mutex<AudioConfig>>,
+    jitter: JitterBuffer,
+}
 
 impl AudioStream {
-    pub fn into_input(self) -> Input {
+    /// Creates an input that is encoded to fit the bitrate of the voice channel
+    pub fn into_input(mut self, channel_bitrate: Option<u64>) -> Input {
         // Clear the stream to minimize latency
-        self.0.lock().clear();
+        self.consumer.lock().clear();
+        self.jitter.reset(self.config.lock().fade_in());
+
+        let encoder = OpusEncoder::new(&self.encoder_config(), channel_bitrate)
+            .expect("Creates opus encoder");
+        let decoder = OpusDecoderState::new().expect("Creates opus decoder");
 
         Input::new(
             true,
-            Reader::Extension(Box::new(self)),
-            Codec::FloatPcm,
-            Container::Raw,
+            Reader::Extension(Box::new(EncodedStream::new(self, encoder))),
+            Codec::Opus(decoder),
+            Container::Dca { first_frame: 0 },
             None,
         )
     }
+
+    pub(super) fn encoder_config(&self) -> EncoderConfig {
+        self.config.lock().encoder.clone()
+    }
 }
 
 impl Read for AudioStream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let mut consumer = self.0.lock();
+        let mut consumer = self.consumer.lock();
+
+        if self.jitter.take_reset_request() {
+            consumer.clear();
+            self.jitter.reset(self.config.lock().fade_in());
+        }
 
         let stereo = SAMPLE_IN_BYTES * 2;
         let safe_length = buf.len() / stereo * stereo;
 
-        consumer
-            .read_exact(&mut buf[..safe_length])
-            .unwrap_or_default();
+        let delay = duration_in_bytes(self.config.lock().delay()).min(MAX_DELAY_SIZE);
+        self.jitter
+            .read(&mut consumer, &mut buf[..safe_length], delay);
 
         Ok(safe_length)
     }
 }
-
-impl Seek for AudioStream {
-    fn seek(&mut self, _: std::io::SeekFrom) -> std::io::Result<u64> {
-        unreachable!()
-    }
-}
-
-impl MediaSource for AudioStream {
-    fn byte_len(&self) -> Option<u64> {
-        None
-    }
-
-    fn is_seekable(&self) -> bool {
-        false
-    }
-}
-
-fn normalize_volume(bytes: &[u8], incoming_volume: f32) -> Vec<u8> {
-    let reciprocal = 1. / incoming_volume;
-    let db_loudness = 10. * reciprocal.log(3.);
-    let signal_factor = 10f32.powf(db_loudness / 20.);
-
-    raw_samples_from_bytes(bytes)
-        .into_iter()
-        .map(|s| s * signal_factor)
-        .flat_map(|s| s.to_le_bytes())
-        .collect()
-}
@@ -0,0 +1,216 @@
+use std::{
+    collections::VecDeque,
+    mem,
+    time::{Duration, Instant},
+};
+
+use crate::state::{AudioConfig, ResamplerQuality};
+
+use super::{fade::Fade, pulse::SinkInputStream, resampler::Resampler, Sample, SAMPLE_RATE};
+
+/// How long to wait for an outgoing stream to finish fading out before dropping it anyway
+pub const OUTGOING_GRACE_PERIOD: Duration = Duration::from_millis(250);
+
+/// How much outgoing audio can be held back while waiting for the new stream to mix with
+const MAX_MIX_BUFFER_LENGTH: usize = SAMPLE_RATE / 10 * 2;
+
+/// Switches between recording streams without audible cuts
+pub struct Transition {
+    current: Option<ActiveStream>,
+
+    /// The previously active stream, kept alive until it has faded out
+    outgoing: Option<ActiveStream>,
+
+    /// Faded audio from the outgoing stream, waiting to be mixed with the current one
+    mix_buffer: VecDeque<Sample>,
+}
+
+/// Resamplers built ahead of time for a new quality, so the audio thread only has to swap them in
+pub struct ResamplerUpdate(Vec<(u32, Resampler)>);
+
+/// A recording stream and the fade applied to it
+struct ActiveStream {
+    stream: SinkInputStream,
+    fade: Fade,
+    resampler: Resampler,
+
+    /// Whether this stream is mixed with the next one while fading out
+    crossfade: bool,
+
+    /// When the stream was created, or when it started fading out
+    changed_at: Instant,
+}
+
+impl Transition {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            outgoing: None,
+            mix_buffer: VecDeque::with_capacity(MAX_MIX_BUFFER_LENGTH * 2),
+        }
+    }
+
+    /// Fades out the current stream and fades in the next one, which comes with a resampler for its rate
+    pub fn switch(&mut self, next: Option<(SinkInputStream, Resampler)>, config: &AudioConfig) {
+        // There is nothing to mix with when stopping, so that is a plain fade out
+        let crossfade = config.crossfade && next.is_some();
+
+        let (fade_out, fade_in) = if crossfade {
+            (config.crossfade(), config.crossfade())
+        } else {
+            (config.fade_out(), config.fade_in())
+        };
+
+        // Whatever is left over belongs to a stream that is being replaced
+        self.mix_buffer.clear();
+
+        // A stream that is still waiting for its turn has not been heard yet, so it can be dropped
+        if let Some(mut previous) = self.current.take().filter(|s| !s.fade.is_silent()) {
+            previous.fade.fade_out(fade_out);
+            previous.crossfade = crossfade;
+            previous.changed_at = Instant::now();
+
+            self.outgoing = Some(previous);
+        }
+
+        self.current = next.map(|(stream, resampler)| {
+            let mut fade = Fade::new(0.);
+
+            if self.outgoing.is_none() || crossfade {
+                fade.fade_in(fade_in);
+            }
+
+            ActiveStream {
+                stream,
+                resampler,
+                fade,
+                crossfade: false,
+                changed_at: Instant::now(),
+            }
+        });
+    }
+
+    /// Applies fades and mixing in place to audio coming from the stream with the given id.
+    /// Returns false if there is nothing to be played.
+    pub fn process(&mut self, id: u32, samples: &mut Vec<Sample>, fade_in: Duration) -> bool {
+        if let Some(previous) = self.outgoing.as_mut().filter(|s| s.stream.id() == id) {
+            previous.resampler.process(samples);
+            previous.fade.apply(samples);
+
+            let crossfade = previous.crossfade;
+
+            if previous.fade.is_silent() {
+                self.finish_outgoing(fade_in);
+            }
+
+            if !crossfade {
+                return true;
+            }
+
+            self.mix_buffer.extend(samples.drain(..));
+
+            // The new stream isn't sending anything, so play the outgoing audio on its own
+            let overflow = self.mix_buffer.len().saturating_sub(MAX_MIX_BUFFER_LENGTH);
+            samples.extend(self.mix_buffer.drain(..overflow));
+
+            return overflow > 0;
+        }
+
+        let current = match self.current.as_mut().filter(|s| s.stream.id() == id) {
+            Some(current) => current,
+            None => return false,
+        };
+
+        // The new stream has to wait until the previous one has faded out
+        if self
+            .outgoing
+            .as_ref()
+            .map(|s| !s.crossfade)
+            .unwrap_or_default()
+        {
+            return false;
+        }
+
+        current.resampler.process(samples);
+        current.fade.apply(samples);
+
+        let mixable = samples.len().min(self.mix_buffer.len());
+
+        for (sample, outgoing) in samples.iter_mut().zip(self.mix_buffer.drain(..mixable)) {
+            *sample += outgoing;
+        }
+
+        true
+    }
+
+    /// The id and native rate of the streams that are playing
+    pub fn streams(&self) -> Vec<(u32, u32)> {
+        self.current
+            .iter()
+            .chain(&self.outgoing)
+            .map(|s| (s.stream.id(), s.stream.rate()))
+            .collect()
+    }
+
+    /// Swaps in prepared resamplers, returning the ones they replace so they can be dropped elsewhere
+    pub fn apply(&mut self, mut update: ResamplerUpdate) -> ResamplerUpdate {
+        for (id, resampler) in &mut update.0 {
+            let stream = self
+                .current
+                .iter_mut()
+                .chain(&mut self.outgoing)
+                .find(|s| s.stream.id() == *id);
+
+            if let Some(stream) = stream {
+                mem::swap(&mut stream.resampler, resampler);
+            }
+        }
+
+        update
+    }
+
+    /// Drops the outgoing stream if it stopped sending audio before it could fade out
+    pub fn drop_stale(&mut self, config: &AudioConfig) {
+        let longest_fade = config.fade_out().max(config.crossfade());
+
+        let is_stale = self
+            .outgoing
+            .as_ref()
+            .map(|s| s.changed_at.elapsed() >= longest_fade + OUTGOING_GRACE_PERIOD)
+            .unwrap_or_default();
+
+        if is_stale {
+            self.finish_outgoing(config.fade_in());
+        }
+    }
+
+    fn finish_outgoing(&mut self, fade_in: Duration) {
+        let previous = self.outgoing.take();
+
+        if let Some(current) = self.current.as_mut() {
+            let was_waiting = previous.map(|p| !p.crossfade).unwrap_or_default();
+
+            if was_waiting {
+                current.fade.fade_in(fade_in);
+            }
+        }
+    }
+}
+
+impl ResamplerUpdate {
+    /// Builds resamplers for the streams with the given ids and native rates
+    pub fn new(streams: &[(u32, u32)], quality: ResamplerQuality) -> Self {
+        let resamplers = streams
+            .iter()
+            .map(|&(id, rate)| (id, Resampler::new(rate, quality)))
+            .collect();
+
+        Self(resamplers)
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
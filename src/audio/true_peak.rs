@@ -0,0 +1,117 @@
+use super::{resampler::windowed_sinc, Sample};
+
+/// How many times the signal is oversampled to find peaks between samples
+const OVERSAMPLING: usize = 4;
+/// Taps of the interpolation filter for each phase
+const TAPS: usize = 12;
+
+/// Finds the peak of a single channel after oversampling, as described in ITU-R BS.1770
+pub struct TruePeak {
+    /// The interpolation filter, `TAPS` coefficients for each phase
+    coefficients: Vec<f32>,
+    /// The most recent samples, oldest first
+    history: [Sample; TAPS],
+
+    /// The highest absolute value found since the last call to [TruePeak::take]
+    peak: f32,
+}
+
+impl TruePeak {
+    pub fn new() -> Self {
+        let half_width = (TAPS / 2) as f64;
+        let mut coefficients = Vec::with_capacity(TAPS * OVERSAMPLING);
+
+        for phase in 0..OVERSAMPLING {
+            let offset = phase as f64 / OVERSAMPLING as f64;
+
+            let taps: Vec<_> = (0..TAPS)
+                .map(|tap| tap as f64 - (half_width - 1.) - offset)
+                .map(|distance| windowed_sinc(distance, 1., half_width))
+                .collect();
+
+            // Each phase passes DC at unity gain, so a constant signal reads the same
+            let sum: f64 = taps.iter().sum();
+            coefficients.extend(taps.iter().map(|c| (c / sum) as f32));
+        }
+
+        Self {
+            coefficients,
+            history: [0.; TAPS],
+            peak: 0.,
+        }
+    }
+
+    pub fn write(&mut self, samples: &[Sample]) {
+        for &sample in samples {
+            self.history.copy_within(1.., 0);
+            self.history[TAPS - 1] = sample;
+
+            for phase in self.coefficients.chunks_exact(TAPS) {
+                let value: f32 = phase.iter().zip(&self.history).map(|(c, s)| c * s).sum();
+                self.peak = self.peak.max(value.abs());
+            }
+        }
+    }
+
+    /// Returns the peak since the last call
+    pub fn take(&mut self) -> f32 {
+        std::mem::take(&mut self.peak)
+    }
+}
+
+impl Default for TruePeak {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn finds_peaks_between_samples() {
+        // A quarter of the sample rate, shifted so every sample lands at 45 degrees
+        let samples: Vec<Sample> = (0..4800)
+            .map(|i| 0.5 * (PI / 2. * i as f32 + PI / 4.).sin())
+            .collect();
+
+        let sample_peak = samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+
+        let mut true_peak = TruePeak::new();
+        true_peak.write(&samples);
+        let true_peak = true_peak.take();
+
+        assert!(true_peak > sample_peak * 1.3);
+        assert!((sample_peak - 0.354).abs() < 0.01);
+        assert!(
+            (true_peak - 0.5).abs() < 0.025,
+            "expected about 0.5, got {true_peak}"
+        );
+    }
+
+    #[test]
+    fn reads_constant_signals_as_is() {
+        let mut true_peak = TruePeak::new();
+
+        // The step up from silence overshoots, like it would on a real converter
+        true_peak.write(&[0.25; 100]);
+        true_peak.take();
+
+        true_peak.write(&[0.25; 100]);
+        assert!((true_peak.take() - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn take_starts_over() {
+        let mut true_peak = TruePeak::new();
+        true_peak.write(&[0.5; 100]);
+        true_peak.write(&[0.; 100]);
+        assert!(true_peak.take() >= 0.5);
+
+        true_peak.write(&[0.; 100]);
+        assert_eq!(true_peak.take(), 0.);
+    }
+}
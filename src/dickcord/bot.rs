@@ -1,6 +1,9 @@
 use super::TargetUser;
 use crate::{audio::AudioStream, state::Config};
-use crossbeam::channel::{unbounded, Receiver, Sender};
+use crossbeam::{
+    atomic::AtomicCell,
+    channel::{unbounded, Receiver, Sender},
+};
 use serenity::{
     async_trait,
     client::{bridge::gateway::ShardManager, Context as SerenityContext, EventHandler},
@@ -16,8 +19,11 @@ use serenity::{
     },
     Client,
 };
-use songbird::{error::JoinError, Call, SerenityInit};
-use std::sync::Arc;
+use songbird::{
+    error::JoinError, tracks::TrackHandle, Call, CoreEvent, Event, EventContext,
+    EventHandler as SongbirdEventHandler, SerenityInit,
+};
+use std::{collections::HashMap, sync::Arc};
 use tokio::{runtime::Runtime, sync::Mutex};
 
 type ConnectedToChannel = Arc<Mutex<Option<GuildChannel>>>;
@@ -34,6 +40,10 @@ pub struct Bot {
     context: Arc<Mutex<Option<SerenityContext>>>,
 
     connected_to_channel: ConnectedToChannel,
+
+    /// The track currently playing in the call
+    track: Mutex<Option<TrackHandle>>,
+    is_paused: AtomicCell<bool>,
 }
 
 /// The event handler for the Serenity client
@@ -45,6 +55,15 @@ pub struct BotHandler {
     connected_to_channel: ConnectedToChannel,
 }
 
+/// Reports when users in the voice channel start or stop talking
+#[derive(Clone)]
+struct SpeakingHandler {
+    event_sender: Sender<BotEvent>,
+
+    /// Maps the SSRC of each audio source in the call to the user it belongs to
+    users: Arc<Mutex<HashMap<u32, u64>>>,
+}
+
 #[derive(Clone)]
 pub enum BotEvent {
     /// Bot has connected to Discord
@@ -61,6 +80,8 @@ pub enum BotEvent {
     TargetUserMoved(Option<GuildChannel>),
     // The user the bot is following started or stopped a live stream
     TargetUserStreamStateChanged(bool),
+    /// A user in the voice channel started or stopped talking
+    SpeakingStateChanged(u64, bool),
     /// Something bad happened, duh.
     ClientError(String),
     /// An error occurred with the voice connection
@@ -108,6 +129,8 @@ impl Bot {
             shard_manager,
             event_receiver,
             connected_to_channel,
+            track: Default::default(),
+            is_paused: Default::default(),
         }
     }
 
@@ -127,15 +150,13 @@ impl Bot {
     }
 
     pub async fn attempt_join_and_stream(&self, audio: AudioStream) {
-        let channel = self.locate_target_user_channel().await;
-
-        let call = match channel {
-            Some(c) => self.connect_to_channel(&c).await,
-            None => None,
+        let channel = match self.locate_target_user_channel().await {
+            Some(c) => c,
+            None => return,
         };
 
-        if let Some(call) = call {
-            self.stream_call_audio(call, audio).await;
+        if let Some(call) = self.connect_to_channel(&channel).await {
+            self.stream_call_audio(call, audio, &channel).await;
         }
     }
 
@@ -174,11 +195,26 @@ impl Bot {
                     .send(BotEvent::Joined(channel.clone()))
                     .unwrap();
 
+                self.listen_for_speaking(&handler).await;
                 Some(handler)
             }
         }
     }
 
+    async fn listen_for_speaking(&self, call: &Arc<Mutex<Call>>) {
+        let mut call = call.lock().await;
+
+        let handler = SpeakingHandler {
+            event_sender: self.event_sender.clone(),
+            users: Default::default(),
+        };
+
+        // Rejoining returns the same call, so get rid of handlers from the previous join
+        call.remove_all_global_events();
+        call.add_global_event(CoreEvent::SpeakingStateUpdate.into(), handler.clone());
+        call.add_global_event(CoreEvent::SpeakingUpdate.into(), handler);
+    }
+
     pub async fn disconnect_from_channel(&self) -> Result<(), JoinError> {
         let context = self.context().await;
         let manager = songbird::get(&context).await.unwrap();
@@ -192,8 +228,41 @@ impl Bot {
         Ok(())
     }
 
-    pub async fn stream_call_audio(&self, call: Arc<Mutex<Call>>, audio: AudioStream) {
-        call.lock().await.play_only_source(audio.into_input());
+    pub async fn stream_call_audio(
+        &self,
+        call: Arc<Mutex<Call>>,
+        audio: AudioStream,
+        channel: &GuildChannel,
+    ) {
+        let track = call
+            .lock()
+            .await
+            .play_only_source(audio.into_input(channel.bitrate));
+
+        if self.is_paused.load() {
+            track.pause().ok();
+        }
+
+        *self.track.lock().await = Some(track);
+    }
+
+    /// Stops sending packets to the call without leaving it
+    pub async fn set_paused(&self, is_paused: bool) {
+        self.is_paused.store(is_paused);
+
+        if let Some(track) = self.track.lock().await.as_ref() {
+            let result = if is_paused {
+                track.pause()
+            } else {
+                track.play()
+            };
+
+            if let Err(e) = result {
+                self.event_sender
+                    .send(BotEvent::VoiceError(e.to_string()))
+                    .unwrap();
+            }
+        }
     }
 
     /// Finds the channel the target user is in, if any
@@ -345,6 +414,31 @@ impl EventHandler for BotHandler {
     }
 }
 
+#[async_trait]
+impl SongbirdEventHandler for SpeakingHandler {
+    async fn act(&self, context: &EventContext<'_>) -> Option<Event> {
+        match context {
+            EventContext::SpeakingStateUpdate(speaking) => {
+                if let Some(user_id) = speaking.user_id {
+                    self.users.lock().await.insert(speaking.ssrc, user_id.0);
+                }
+            }
+            EventContext::SpeakingUpdate(data) => {
+                let user_id = self.users.lock().await.get(&data.ssrc).copied();
+
+                if let Some(user_id) = user_id {
+                    self.event_sender
+                        .send(BotEvent::SpeakingStateChanged(user_id, data.speaking))
+                        .unwrap();
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
 fn intents() -> GatewayIntents {
     GatewayIntents::GUILDS
         | GatewayIntents::GUILD_MEMBERS
@@ -8,7 +8,7 @@ use super::{Bot, BotEvent};
 use crossbeam::{atomic::AtomicCell, channel::Sender};
 use parking_lot::Mutex;
 use serenity::model::{channel::GuildChannel, user::CurrentUser};
-use std::{sync::Arc, thread, time::Duration};
+use std::{collections::HashSet, sync::Arc, thread, time::Duration};
 use tokio::runtime::Runtime;
 
 /// Manages all discord related things
@@ -20,6 +20,14 @@ pub struct DiscordSystem {
     state: Mutex<State>,
     is_streaming: AtomicCell<bool>,
 
+    /// The name of the source being streamed, shown in the presence
+    source_name: Mutex<Option<String>>,
+    is_paused: AtomicCell<bool>,
+
+    /// Users in the voice channel that are currently talking
+    speaking_users: Mutex<HashSet<u64>>,
+    is_ducking: AtomicCell<bool>,
+
     config: Mutex<Option<ReadOnlyConfig>>,
     stream: AudioStream,
 }
@@ -51,6 +59,10 @@ impl DiscordSystem {
             bot: Default::default(),
             state: Default::default(),
             is_streaming: Default::default(),
+            source_name: Default::default(),
+            is_paused: Default::default(),
+            speaking_users: Default::default(),
+            is_ducking: Default::default(),
             config: Default::default(),
             app_events,
         });
@@ -79,8 +91,30 @@ impl DiscordSystem {
     }
 
     pub fn announce_source_streaming(&self, source: Option<Source>) {
+        *self.source_name.lock() = source.map(|s| s.name());
+        self.update_presence();
+    }
+
+    pub fn set_paused(&self, is_paused: bool) {
+        self.is_paused.store(is_paused);
+
+        if let Some(bot) = self.bot.lock().clone() {
+            self.rt
+                .spawn(async move { bot.set_paused(is_paused).await });
+        }
+
+        self.update_presence();
+    }
+
+    fn update_presence(&self) {
         let bot = self.bot_unwrapped();
-        let name = source.map(|s| s.name());
+        let name = self.source_name.lock().clone().map(|name| {
+            if self.is_paused.load() {
+                format!("{} (paused)", name)
+            } else {
+                name
+            }
+        });
 
         self.rt
             .spawn(async move { bot.set_streaming_status(name).await });
@@ -140,6 +174,9 @@ impl DiscordSystem {
             BotEvent::TargetUserStreamStateChanged(new_state) => {
                 self.handle_target_user_stream_state_changed(new_state)
             }
+            BotEvent::SpeakingStateChanged(user_id, is_speaking) => {
+                self.handle_speaking_state_changed(user_id, is_speaking)
+            }
             BotEvent::Reconnected => {}
         }
     }
@@ -169,7 +206,8 @@ impl DiscordSystem {
     }
 
     fn handle_left(&self) {
-        self.set_voice_state(VoiceState::Idle)
+        self.set_voice_state(VoiceState::Idle);
+        self.clear_speaking_users();
     }
 
     fn handle_voice_error(&self, error: String) {
@@ -177,6 +215,8 @@ impl DiscordSystem {
     }
 
     fn handle_target_user_moved(&self, new_channel: Option<GuildChannel>) {
+        self.clear_speaking_users();
+
         if let Some(new_channel) = new_channel {
             self.set_voice_state(VoiceState::Active(new_channel));
             self.stream_on_demand();
@@ -194,6 +234,42 @@ impl DiscordSystem {
         self.stream_on_demand();
     }
 
+    fn handle_speaking_state_changed(&self, user_id: u64, is_speaking: bool) {
+        {
+            let mut speaking_users = self.speaking_users.lock();
+
+            if is_speaking {
+                speaking_users.insert(user_id);
+            } else {
+                speaking_users.remove(&user_id);
+            }
+        }
+
+        self.update_ducking();
+    }
+
+    fn clear_speaking_users(&self) {
+        self.speaking_users.lock().clear();
+        self.update_ducking();
+    }
+
+    fn update_ducking(&self) {
+        let config = self.config_unwrapped();
+        let speaking_users = self.speaking_users.lock();
+
+        let should_duck = if config.audio.ducking.target_user_only {
+            speaking_users.contains(&config.user_id)
+        } else {
+            !speaking_users.is_empty()
+        };
+
+        if self.is_ducking.swap(should_duck) != should_duck {
+            self.app_events
+                .send(AppEvent::DuckingStateUpdate(should_duck))
+                .unwrap();
+        }
+    }
+
     fn bot_unwrapped(&self) -> Arc<Bot> {
         self.bot
             .lock()
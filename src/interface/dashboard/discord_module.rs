@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
@@ -11,6 +13,9 @@ use crate::{
     interface::View,
 };
 
+/// How long the AGC line stays red after a clip
+const CLIP_HIGHLIGHT: Duration = Duration::from_secs(2);
+
 pub struct DiscordModule {
     context: AppContext,
 }
@@ -40,7 +45,13 @@ impl View for DiscordModule {
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
             .split(block_inner);
 
         block.render(area, buf);
@@ -83,10 +94,63 @@ impl View for DiscordModule {
             }
 
             if let VoiceState::Active(channel) = &voice_state {
-                let paragraph = Paragraph::new(format!("└ 🔊\u{FE0E} {}", channel.name()))
-                    .style(Style::default().fg(Color::Green));
+                let paragraph = if self.context.is_paused() {
+                    Paragraph::new(format!("└ ⏸\u{FE0E} {} (paused)", channel.name()))
+                        .style(Style::default().fg(Color::Yellow))
+                } else {
+                    Paragraph::new(format!("└ 🔊\u{FE0E} {}", channel.name()))
+                        .style(Style::default().fg(Color::Green))
+                };
 
                 paragraph.render(chunks[1], buf);
+
+                let stats = self.context.buffer_stats();
+
+                let paragraph = Paragraph::new(format!(
+                    "  Buffer {}/{} ms",
+                    stats.latency.as_millis(),
+                    stats.target.as_millis(),
+                ))
+                .style(Style::default().fg(Color::DarkGray));
+
+                paragraph.render(chunks[2], buf);
+
+                let color = if stats.underruns > 0 || stats.overruns > 0 || stats.dropped > 0 {
+                    Color::Yellow
+                } else {
+                    Color::DarkGray
+                };
+
+                let paragraph = Paragraph::new(format!(
+                    "  {} under · {} over · {} dropped",
+                    stats.underruns, stats.overruns, stats.dropped
+                ))
+                .style(Style::default().fg(color));
+
+                paragraph.render(chunks[3], buf);
+
+                if self.context.config().audio.agc.enabled {
+                    let agc = self.context.agc_stats();
+
+                    let has_clipped_recently = agc
+                        .last_clip
+                        .map(|t| t.elapsed() < CLIP_HIGHLIGHT)
+                        .unwrap_or_default();
+
+                    let color = if has_clipped_recently {
+                        Color::Red
+                    } else {
+                        Color::DarkGray
+                    };
+
+                    let paragraph = Paragraph::new(format!(
+                        "  AGC {:+.1} dB · {} clips",
+                        agc.gain_db, agc.clips
+                    ))
+                    .style(Style::default().fg(color));
+
+                    paragraph.render(chunks[4], buf);
+                }
             }
         }
     }
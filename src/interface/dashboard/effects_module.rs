@@ -0,0 +1,247 @@
+use crossterm::event::{Event, KeyCode};
+use tui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    app::{AppAction, AppContext},
+    audio::effects::ParameterInfo,
+    interface::View,
+};
+
+/// Lists the effect chain, with the parameters of each effect below it
+pub struct EffectsModule {
+    context: AppContext,
+    selected_row: usize,
+    focused: bool,
+}
+
+enum Row {
+    Effect {
+        index: usize,
+        name: String,
+        bypassed: bool,
+        /// Why the effect can't be used, if it can't
+        error: Option<String>,
+    },
+    Parameter {
+        index: usize,
+        info: ParameterInfo,
+        value: f32,
+    },
+}
+
+impl EffectsModule {
+    pub fn new(context: AppContext) -> Self {
+        Self {
+            context,
+            selected_row: 0,
+            focused: false,
+        }
+    }
+
+    /// How many lines the module needs to show everything
+    pub fn height(&self) -> u16 {
+        self.rows().len() as u16 + 3
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let config = self.context.config();
+        let mut rows = vec![];
+
+        for (index, effect) in config.audio.effects.iter().enumerate() {
+            rows.push(Row::Effect {
+                index,
+                name: effect.kind.name(),
+                bypassed: effect.bypassed,
+                error: effect.kind.error(),
+            });
+
+            for info in effect.kind.parameters() {
+                rows.push(Row::Parameter {
+                    index,
+                    value: effect.value(&info),
+                    info,
+                });
+            }
+        }
+
+        rows
+    }
+
+    fn navigate(&mut self, amount: isize) {
+        let length = self.rows().len().max(1) as isize;
+        self.selected_row = (self.selected_row as isize + amount).rem_euclid(length) as usize;
+    }
+
+    fn activate(&self) {
+        let action = match self.rows().into_iter().nth(self.selected_row) {
+            Some(Row::Effect { index, .. }) => AppAction::ToggleEffect(index),
+            Some(Row::Parameter { index, info, .. }) => {
+                AppAction::SetEffectParameter(index, info.name, info.default)
+            }
+            None => return,
+        };
+
+        self.context.dispatch_action(action);
+    }
+
+    fn adjust(&self, amount: f32) {
+        if let Some(Row::Parameter { index, info, value }) =
+            self.rows().into_iter().nth(self.selected_row)
+        {
+            let value = (value + info.step * amount).clamp(info.min, info.max);
+
+            self.context
+                .dispatch_action(AppAction::SetEffectParameter(index, info.name, value));
+        }
+    }
+
+    /// Moves the selected effect, keeping it selected
+    fn move_effect(&mut self, amount: isize) {
+        let index = match self.rows().into_iter().nth(self.selected_row) {
+            Some(Row::Effect { index, .. }) | Some(Row::Parameter { index, .. }) => index,
+            None => return,
+        };
+
+        // Amount of rows each effect takes up, in the order they will be in after moving
+        let mut row_counts: Vec<_> = self
+            .context
+            .config()
+            .audio
+            .effects
+            .iter()
+            .map(|e| e.kind.parameters().len() + 1)
+            .collect();
+
+        let new_index = index.saturating_add_signed(amount);
+
+        if new_index == index || new_index >= row_counts.len() {
+            return;
+        }
+
+        row_counts.swap(index, new_index);
+        self.selected_row = row_counts[..new_index].iter().sum();
+
+        self.context
+            .dispatch_action(AppAction::MoveEffect(index, amount));
+    }
+
+    pub fn focus(&mut self) {
+        self.focused = true;
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = false;
+    }
+}
+
+impl View for EffectsModule {
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title("─ Effects ")
+            .borders(Borders::all());
+
+        let block_inner = {
+            let area = block.inner(area);
+            Rect::new(
+                area.left() + 2,
+                area.top() + 1,
+                area.width.saturating_sub(3),
+                area.height.saturating_sub(1),
+            )
+        };
+
+        block.render(area, buf);
+
+        let rows = self.rows();
+
+        // Scroll so the selected row is always visible
+        let visible = (block_inner.height as usize).max(1);
+        let offset = (self.selected_row + 1).saturating_sub(visible);
+
+        for (line, (row_index, row)) in rows
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .enumerate()
+        {
+            let row_area = Rect::new(
+                block_inner.left(),
+                block_inner.top() + line as u16,
+                block_inner.width,
+                1,
+            );
+
+            let focus_symbol = if self.focused && row_index == self.selected_row {
+                FOCUS_SYMBOL
+            } else {
+                IDLE_SYMBOL
+            };
+
+            let (label, value, style) = match row {
+                Row::Effect {
+                    name,
+                    bypassed,
+                    error,
+                    ..
+                } => {
+                    let (value, color) = if error.is_some() {
+                        ("N/A", Color::Yellow)
+                    } else if *bypassed {
+                        ("OFF", Color::DarkGray)
+                    } else {
+                        ("ON", Color::Green)
+                    };
+
+                    (
+                        format!("{} {}", focus_symbol, name),
+                        value.to_owned(),
+                        Style::default().fg(color),
+                    )
+                }
+                Row::Parameter { info, value, .. } => (
+                    format!("  {} {}", focus_symbol, info.name),
+                    format!("{:.1} {}", value, info.unit),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            };
+
+            let value_width = (value.len() as u16).max(3);
+
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Length(row_area.width.saturating_sub(value_width)),
+                    Constraint::Length(value_width),
+                ])
+                .split(row_area);
+
+            Paragraph::new(label).render(chunks[0], buf);
+            Paragraph::new(value).style(style).render(chunks[1], buf);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Up => self.navigate(-1),
+                KeyCode::Down => self.navigate(1),
+                KeyCode::Enter => self.activate(),
+                KeyCode::Char('+') | KeyCode::Char('=') => self.adjust(1.),
+                KeyCode::Char('-') => self.adjust(-1.),
+                KeyCode::Char('[') => self.move_effect(-1),
+                KeyCode::Char(']') => self.move_effect(1),
+                _ => {}
+            }
+        }
+    }
+}
+
+const IDLE_SYMBOL: &str = "○";
+const FOCUS_SYMBOL: &str = "●";
@@ -0,0 +1,124 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    symbols::Marker,
+    text::Span,
+    widgets::{
+        canvas::{Canvas, Line},
+        Block, Borders, Widget,
+    },
+};
+
+use crate::{app::AppContext, audio::HISTORY_LENGTH, interface::View};
+
+/// The lowest level shown, in dB
+const MIN_DB: f64 = -60.;
+
+/// Levels marked across the graph, in dB
+const GRID_DB: [f64; 4] = [-48., -36., -24., -12.];
+
+/// Peaks above this are marked as too loud, in dBFS
+const LOUD_PEAK_DB: f32 = -1.;
+
+const LOUDNESS_COLOR: Color = Color::Rgb(82, 224, 45);
+const PEAK_COLOR: Color = Color::Rgb(90, 90, 90);
+const LOUD_PEAK_COLOR: Color = Color::Rgb(199, 54, 28);
+
+/// Shows the short-term loudness and peak level of every second over the last 10 minutes
+pub struct LoudnessHistory {
+    context: AppContext,
+}
+
+impl LoudnessHistory {
+    pub fn new(context: AppContext) -> Self {
+        Self { context }
+    }
+}
+
+impl View for LoudnessHistory {
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let points = self.context.loudness_history();
+
+        // The newest point is always on the right edge
+        let start = (HISTORY_LENGTH - points.len()) as f64;
+
+        let block = Block::default()
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title("─ Loudness History ")
+            .borders(Borders::all());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        Canvas::default()
+            .marker(Marker::Braille)
+            .x_bounds([0., HISTORY_LENGTH as f64])
+            .y_bounds([MIN_DB, 0.])
+            .paint(|ctx| {
+                for db in GRID_DB {
+                    ctx.draw(&Line {
+                        x1: 0.,
+                        y1: db,
+                        x2: HISTORY_LENGTH as f64,
+                        y2: db,
+                        color: Color::DarkGray,
+                    });
+                }
+
+                ctx.layer();
+
+                for (i, point) in points.iter().enumerate() {
+                    if !point.peak.is_finite() {
+                        continue;
+                    }
+
+                    let color = if point.peak >= LOUD_PEAK_DB {
+                        LOUD_PEAK_COLOR
+                    } else {
+                        PEAK_COLOR
+                    };
+
+                    let x = start + i as f64;
+
+                    ctx.draw(&Line {
+                        x1: x,
+                        y1: MIN_DB,
+                        x2: x,
+                        y2: (point.peak as f64).max(MIN_DB),
+                        color,
+                    });
+                }
+
+                ctx.layer();
+
+                for (i, pair) in points.windows(2).enumerate() {
+                    let (from, to) = (pair[0].short_term, pair[1].short_term);
+
+                    // Silence leaves a gap instead of a line to the bottom
+                    if !from.is_finite() || !to.is_finite() {
+                        continue;
+                    }
+
+                    let x = start + i as f64;
+
+                    ctx.draw(&Line {
+                        x1: x,
+                        y1: (from as f64).max(MIN_DB),
+                        x2: x + 1.,
+                        y2: (to as f64).max(MIN_DB),
+                        color: LOUDNESS_COLOR,
+                    });
+                }
+
+                for db in GRID_DB {
+                    ctx.print(
+                        0.,
+                        db,
+                        Span::styled(format!("{}", db), Style::default().fg(Color::DarkGray)),
+                    );
+                }
+            })
+            .render(inner, buf);
+    }
+}
@@ -1,6 +1,8 @@
 use tui::{
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
+    widgets::{Paragraph, Widget},
 };
 
 use crate::{app::AppContext, interface::View};
@@ -14,9 +16,18 @@ impl Meter {
         Self { context }
     }
 
-    fn render_meter(&self, value: f32, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
-        let value = value.min(1.);
-        let bar_width = area.width as f32 * value;
+    fn render_meter(
+        &self,
+        dbfs: f32,
+        peak_hold_db: f32,
+        clipped: bool,
+        area: tui::layout::Rect,
+        buf: &mut tui::buffer::Buffer,
+    ) {
+        let indicator_x = area.right().saturating_sub(1);
+        let area = Rect::new(area.x, area.y, area.width.saturating_sub(2), area.height);
+
+        let bar_width = area.width as f32 * position(dbfs);
 
         let amount_of_full_characters = bar_width.floor() as usize;
 
@@ -38,52 +49,203 @@ impl Meter {
             Style::default(),
         );
 
-        let mut bar_area = Rect::new(area.x, area.y, area.width, 1);
+        // Each cell is colored by the level it stands for
+        for x in 0..area.width {
+            let db = MIN_DB * (1. - x as f32 / area.width as f32);
+
+            let style = if db >= RED_ZONE_DB {
+                Style::default()
+                    .bg(Color::Rgb(17, 10, 9))
+                    .fg(Color::Rgb(199, 54, 28))
+            } else if db >= YELLOW_ZONE_DB {
+                Style::default()
+                    .fg(Color::Rgb(255, 240, 85))
+                    .bg(Color::Rgb(17, 16, 9))
+            } else {
+                Style::default()
+                    .fg(Color::Rgb(82, 224, 45))
+                    .bg(Color::Rgb(10, 17, 9))
+            };
+
+            buf.set_style(Rect::new(area.x + x, area.y, 1, 1), style);
+        }
+
+        // The held peak is drawn over the bar, so it stays visible when the bar falls below it
+        let peak_hold_x = (area.width as f32 * position(peak_hold_db)) as u16;
+
+        if peak_hold_db > MIN_DB {
+            buf.set_string(
+                area.x + peak_hold_x.min(area.width.saturating_sub(1)),
+                area.y,
+                PEAK_HOLD_SYMBOL,
+                Style::default().fg(Color::White),
+            );
+        }
+
+        let (symbol, color) = if clipped {
+            (CLIP_SYMBOL, Color::Rgb(199, 54, 28))
+        } else {
+            (NO_CLIP_SYMBOL, Color::DarkGray)
+        };
 
-        buf.set_style(
-            bar_area,
-            Style::default()
-                .fg(Color::Rgb(82, 224, 45))
-                .bg(Color::Rgb(10, 17, 9)),
+        buf.set_string(
+            indicator_x,
+            area.y,
+            symbol,
+            Style::default().fg(color).bg(Color::Reset),
         );
+    }
 
-        let yellow_size = (area.width as f32 * 0.4) as u16;
+    /// Shows how the channels relate, between the bars
+    fn render_correlation(&self, area: Rect, buf: &mut Buffer) {
+        let correlation = self.context.correlation();
+        let balance = self.context.balance();
 
-        bar_area.width = yellow_size;
-        bar_area.x = area.width - yellow_size;
-        buf.set_style(
-            bar_area,
-            Style::default()
-                .fg(Color::Rgb(255, 240, 85))
-                .bg(Color::Rgb(17, 16, 9)),
-        );
+        let balance = if balance.abs() < 0.5 {
+            "C".to_owned()
+        } else if balance > 0. {
+            format!("R {:.1} dB", balance)
+        } else {
+            format!("L {:.1} dB", -balance)
+        };
 
-        let red_size = (area.width as f32 * 0.1) as u16;
+        let readout = format!(" {:+.2}  {}", correlation, balance);
+        let width = area
+            .width
+            .saturating_sub(2 + CORRELATION_READOUT_WIDTH)
+            .max(1);
 
-        bar_area.width = red_size;
-        bar_area.x = (area.width - red_size) + 2;
+        let line_style = Style::default().fg(Color::DarkGray);
 
-        buf.set_style(
-            bar_area,
-            Style::default()
-                .bg(Color::Rgb(17, 10, 9))
-                .fg(Color::Rgb(199, 54, 28)),
+        buf.set_string(area.x, area.y, "─".repeat(width as usize), line_style);
+        buf.set_string(area.x + width / 2, area.y, "┼", line_style);
+
+        let color = if correlation < -0.2 {
+            Color::Rgb(199, 54, 28)
+        } else if correlation < 0. {
+            Color::Rgb(255, 240, 85)
+        } else {
+            Color::Rgb(82, 224, 45)
+        };
+
+        let marker_x = ((correlation + 1.) / 2. * (width - 1) as f32).round() as u16;
+
+        buf.set_string(
+            area.x + marker_x.min(width - 1),
+            area.y,
+            CORRELATION_SYMBOL,
+            Style::default().fg(color),
         );
+
+        buf.set_string(area.x + width, area.y, readout, line_style);
+    }
+
+    /// Labels the bars with their levels
+    fn render_ticks(&self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.saturating_sub(2);
+        let mut free_from = area.x;
+
+        for db in TICKS_DB {
+            let label = format!("{}", db);
+            let label_width = label.len() as u16;
+
+            let x = area.x + (width as f32 * position(db as f32)) as u16;
+
+            // Labels start at their tick, except for the ones that would run off the end
+            let x = x.min(area.x + width.saturating_sub(label_width));
+
+            if x < free_from {
+                continue;
+            }
+
+            buf.set_string(x, area.y, &label, Style::default().fg(Color::DarkGray));
+            free_from = x + label_width + 1;
+        }
+    }
+
+    fn render_loudness(&self, area: Rect, buf: &mut Buffer) {
+        let loudness = self.context.loudness();
+
+        let readouts = [
+            ("M", loudness.momentary),
+            ("S", loudness.short_term),
+            ("I", loudness.integrated),
+        ];
+
+        for (row, (label, value)) in readouts.into_iter().enumerate() {
+            let value = if value.is_finite() {
+                format!("{:>6.1}", value)
+            } else {
+                format!("{:>6}", "-inf")
+            };
+
+            let line = Paragraph::new(format!("{} {} LUFS", label, value))
+                .style(Style::default().fg(Color::DarkGray));
+
+            let row_area = Rect::new(area.x, area.y + row as u16, area.width, 1);
+            line.render(row_area, buf);
+        }
     }
 }
 
 impl View for Meter {
     fn render(&self, area: Rect, buf: &mut tui::buffer::Buffer) {
-        let (left, right) = self.context.meter_value_ranged();
+        let (left, right) = self.context.meter_dbfs();
+        let (left_hold, right_hold) = self.context.peak_hold_db();
+        let (left_clipped, right_clipped) = self.context.clipped();
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(area.width.saturating_sub(LOUDNESS_WIDTH + 3)),
+                Constraint::Length(3),
+                Constraint::Length(LOUDNESS_WIDTH),
+            ])
+            .split(area);
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(2), Constraint::Length(1)])
-            .split(area);
-
-        self.render_meter(left, chunks[0], buf);
-        self.render_meter(right, chunks[1], buf);
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .split(columns[0]);
+
+        self.render_meter(left, left_hold, left_clipped, chunks[0], buf);
+        self.render_correlation(chunks[1], buf);
+        self.render_meter(right, right_hold, right_clipped, chunks[2], buf);
+        self.render_ticks(chunks[3], buf);
+        self.render_loudness(columns[2], buf);
     }
 }
 
+/// The lowest level the bars show, in dBFS
+const MIN_DB: f32 = -60.;
+
+/// Where the bars turn yellow and red, in dBFS
+const YELLOW_ZONE_DB: f32 = -18.;
+const RED_ZONE_DB: f32 = -6.;
+
+const TICKS_DB: [i32; 9] = [-60, -48, -36, -24, -18, -12, -6, -3, 0];
+
+/// Where a level is on the bars, from 0 to 1
+fn position(db: f32) -> f32 {
+    (1. - db / MIN_DB).clamp(0., 1.)
+}
+
+/// Room for a readout like " -0.35  L 12.5 dB"
+const CORRELATION_READOUT_WIDTH: u16 = 17;
+const CORRELATION_SYMBOL: &str = "●";
+
+/// Room for a readout like "I  -23.0 LUFS"
+const LOUDNESS_WIDTH: u16 = 13;
+
+const PEAK_HOLD_SYMBOL: &str = "▏";
+
+/// Latched until the clip indicators are reset
+const CLIP_SYMBOL: &str = "●";
+const NO_CLIP_SYMBOL: &str = "○";
+
 const BAR_PARTIALS: [&str; 9] = [" ", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
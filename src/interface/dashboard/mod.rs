@@ -19,10 +19,22 @@ use discord_module::*;
 mod settings_module;
 use settings_module::*;
 
+mod effects_module;
+use effects_module::*;
+
+mod spectrum;
+use spectrum::*;
+
+mod scope;
+use scope::*;
+
+mod history;
+use history::*;
+
 mod version;
 use version::*;
 
-use crate::app::AppContext;
+use crate::app::{AppAction, AppContext};
 
 use super::{View, LOGO};
 
@@ -36,14 +48,22 @@ pub struct Content {
     selector_module: SourceSelector,
     discord_module: DiscordModule,
     settings_module: SettingsModule,
+    effects_module: EffectsModule,
     focused_module: FocusedModule,
     meter: Meter,
+    spectrum: Spectrum,
+    scope: Scope,
+    history: LoudnessHistory,
 }
 
+/// How many lines the optional panels take up, including their border
+const PANEL_HEIGHT: u16 = 10;
+
 #[derive(Debug, Default, PartialEq, Sequence)]
 enum FocusedModule {
     #[default]
     SourceSelector,
+    EffectsModule,
     SettingsModule,
 }
 
@@ -58,8 +78,12 @@ impl Dashboard {
                 selector_module,
                 discord_module: DiscordModule::new(context.clone()),
                 settings_module: SettingsModule::new(context.clone()),
+                effects_module: EffectsModule::new(context.clone()),
                 focused_module: Default::default(),
                 meter: Meter::new(context.clone()),
+                spectrum: Spectrum::new(context.clone()),
+                scope: Scope::new(context.clone()),
+                history: LoudnessHistory::new(context.clone()),
                 context,
             },
         }
@@ -125,10 +149,12 @@ impl Content {
         self.focused_module = next_cycle(&self.focused_module).expect("Implements sequence");
 
         self.selector_module.blur();
+        self.effects_module.blur();
         self.settings_module.blur();
 
         match self.focused_module {
             FocusedModule::SourceSelector => self.selector_module.focus(),
+            FocusedModule::EffectsModule => self.effects_module.focus(),
             FocusedModule::SettingsModule => self.settings_module.focus(),
         }
     }
@@ -168,12 +194,61 @@ impl View for Content {
         let sidebar_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(6),
-                Constraint::Length(sidebar_area.height.saturating_sub(7)),
+                Constraint::Length(8),
+                Constraint::Length(sidebar_area.height.saturating_sub(9)),
             ])
             .split(sidebar_area);
 
+        // The effects share the main area with the sources, taking up at most half of it
+        let main_area = main_chunks[0];
+        let effects_height = self.effects_module.height().min(main_area.height / 2);
+
+        // Optional panels share a row below the effects
+        let mut panels: Vec<&dyn View> = vec![];
+
+        if config.show_spectrum {
+            panels.push(&self.spectrum);
+        }
+
+        if config.show_scope {
+            panels.push(&self.scope);
+        }
+
+        if config.show_history {
+            panels.push(&self.history);
+        }
+
+        let panels_height = if panels.is_empty() { 0 } else { PANEL_HEIGHT };
+
+        let main_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(
+                    main_area
+                        .height
+                        .saturating_sub(effects_height + panels_height),
+                ),
+                Constraint::Length(effects_height),
+                Constraint::Length(panels_height),
+            ])
+            .split(main_area);
+
         self.selector_module.render(main_chunks[0], buf);
+        self.effects_module.render(main_chunks[1], buf);
+
+        if !panels.is_empty() {
+            let panel_width = main_chunks[2].width / panels.len() as u16;
+
+            let panel_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Length(panel_width); panels.len()])
+                .split(main_chunks[2]);
+
+            for (panel, area) in panels.iter().zip(panel_chunks) {
+                panel.render(area, buf);
+            }
+        }
+
         self.settings_module.render(sidebar_chunks[1], buf);
         self.discord_module.render(sidebar_chunks[0], buf);
 
@@ -193,10 +268,26 @@ impl View for Content {
                 self.cycle_focus();
                 return;
             }
+
+            if key.code == KeyCode::Char('p') {
+                self.context.dispatch_action(AppAction::TogglePause);
+                return;
+            }
+
+            if key.code == KeyCode::Char('r') {
+                self.context.dispatch_action(AppAction::ResetLoudness);
+                return;
+            }
+
+            if key.code == KeyCode::Char('c') {
+                self.context.dispatch_action(AppAction::ResetClips);
+                return;
+            }
         }
 
         match self.focused_module {
             FocusedModule::SourceSelector => self.selector_module.handle_event(event),
+            FocusedModule::EffectsModule => self.effects_module.handle_event(event),
             FocusedModule::SettingsModule => self.settings_module.handle_event(event),
         }
     }
@@ -0,0 +1,141 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use tui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Line, Points},
+        Block, Borders, Widget,
+    },
+};
+
+use crate::{app::AppContext, interface::View};
+
+/// Where each channel is centered in the waveform, left on top and right below
+const CHANNEL_OFFSETS: [f64; 2] = [1., -1.];
+
+const WAVEFORM_COLOR: Color = Color::Rgb(82, 224, 45);
+const CLIP_COLOR: Color = Color::Rgb(199, 54, 28);
+
+/// Shows a scrolling waveform of each channel, with a goniometer next to it
+pub struct Scope {
+    context: AppContext,
+}
+
+impl Scope {
+    pub fn new(context: AppContext) -> Self {
+        Self { context }
+    }
+
+    fn render_waveform(&self, area: Rect, buf: &mut Buffer) {
+        let envelopes = self.context.waveform();
+
+        Canvas::default()
+            .marker(Marker::Braille)
+            .x_bounds([0., envelopes.len() as f64])
+            .y_bounds([-2., 2.])
+            .paint(|ctx| {
+                for offset in CHANNEL_OFFSETS {
+                    ctx.draw(&Line {
+                        x1: 0.,
+                        y1: offset,
+                        x2: envelopes.len() as f64,
+                        y2: offset,
+                        color: Color::DarkGray,
+                    });
+                }
+
+                ctx.layer();
+
+                for (x, envelope) in envelopes.iter().enumerate() {
+                    for (channel, offset) in CHANNEL_OFFSETS.iter().enumerate() {
+                        let min = envelope.min[channel] as f64;
+                        let max = envelope.max[channel] as f64;
+
+                        let color = if min <= -1. || max >= 1. {
+                            CLIP_COLOR
+                        } else {
+                            WAVEFORM_COLOR
+                        };
+
+                        ctx.draw(&Line {
+                            x1: x as f64,
+                            y1: offset + min.clamp(-1., 1.),
+                            x2: x as f64,
+                            y2: offset + max.clamp(-1., 1.),
+                            color,
+                        });
+                    }
+                }
+            })
+            .render(area, buf);
+    }
+
+    fn render_goniometer(&self, area: Rect, buf: &mut Buffer) {
+        // Rotated by 45 degrees, so mono is a vertical line and out of phase is horizontal
+        let points: Vec<_> = self
+            .context
+            .recent_frames()
+            .into_iter()
+            .map(|[left, right]| {
+                let (left, right) = (left as f64, right as f64);
+
+                (
+                    ((left - right) * FRAC_1_SQRT_2).clamp(-1., 1.),
+                    ((left + right) * FRAC_1_SQRT_2).clamp(-1., 1.),
+                )
+            })
+            .collect();
+
+        Canvas::default()
+            .marker(Marker::Braille)
+            .x_bounds([-1., 1.])
+            .y_bounds([-1., 1.])
+            .paint(|ctx| {
+                ctx.draw(&Line {
+                    x1: 0.,
+                    y1: -1.,
+                    x2: 0.,
+                    y2: 1.,
+                    color: Color::DarkGray,
+                });
+
+                ctx.layer();
+
+                ctx.draw(&Points {
+                    coords: &points,
+                    color: WAVEFORM_COLOR,
+                });
+            })
+            .render(area, buf);
+    }
+}
+
+impl View for Scope {
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title("─ Scope ")
+            .borders(Borders::all());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Terminal cells are about twice as tall as they are wide
+        let goniometer_width = (inner.height * 2).min(inner.width / 2);
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(inner.width.saturating_sub(goniometer_width + 1)),
+                Constraint::Length(1),
+                Constraint::Length(goniometer_width),
+            ])
+            .split(inner);
+
+        self.render_waveform(chunks[0], buf);
+        self.render_goniometer(chunks[2], buf);
+    }
+}
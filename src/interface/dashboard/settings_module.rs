@@ -1,4 +1,5 @@
 use crossterm::event::{Event, KeyCode};
+use enum_iterator::{next, previous};
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
@@ -9,6 +10,7 @@ use tui::{
 use crate::{
     app::{AppAction, AppContext},
     interface::View,
+    state::{EncoderMode, MeterBallistics, ReadOnlyConfig},
 };
 
 struct Option {
@@ -22,6 +24,11 @@ struct Option {
 enum OptionKind {
     Switch(bool),
     Button,
+    /// A value that is adjusted with the - and + keys
+    Value {
+        display: fn(&ReadOnlyConfig) -> String,
+        adjust: fn(&ReadOnlyConfig, i64) -> AppAction,
+    },
 }
 
 pub struct SettingsModule {
@@ -42,12 +49,159 @@ impl SettingsModule {
                     OptionKind::Switch(config.show_meter),
                     AppAction::ToggleMeter,
                 ),
+                Option::new(
+                    context.clone(),
+                    "Show Spectrum".to_owned(),
+                    OptionKind::Switch(config.show_spectrum),
+                    AppAction::ToggleSpectrum,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Show Scope".to_owned(),
+                    OptionKind::Switch(config.show_scope),
+                    AppAction::ToggleScope,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Show Loudness History".to_owned(),
+                    OptionKind::Switch(config.show_history),
+                    AppAction::ToggleHistory,
+                ),
                 Option::new(
                     context.clone(),
                     "Screenshare Only".to_owned(),
                     OptionKind::Switch(config.screen_share_only),
                     AppAction::ToggleScreenshareOnly,
                 ),
+                Option::new(
+                    context.clone(),
+                    "Crossfade".to_owned(),
+                    OptionKind::Switch(config.audio.crossfade),
+                    AppAction::ToggleCrossfade,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Duck When Talking".to_owned(),
+                    OptionKind::Switch(config.audio.ducking.enabled),
+                    AppAction::ToggleDucking,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Automatic Gain".to_owned(),
+                    OptionKind::Switch(config.audio.agc.enabled),
+                    AppAction::ToggleAgc,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Delay".to_owned(),
+                    OptionKind::Value {
+                        display: |config| format!("{} ms", config.audio.delay_ms),
+                        adjust: |config, amount| {
+                            let delay_ms = config
+                                .audio
+                                .delay_ms
+                                .saturating_add_signed(amount * DELAY_STEP_MS);
+
+                            AppAction::SetDelay(delay_ms)
+                        },
+                    },
+                    AppAction::SetDelay(0),
+                ),
+                Option::new(
+                    context.clone(),
+                    "Bitrate".to_owned(),
+                    OptionKind::Value {
+                        display: |config| match config.audio.encoder.bitrate_kbps {
+                            Some(kbps) => format!("{} kbps", kbps),
+                            None => "Channel".to_owned(),
+                        },
+                        adjust: |config, amount| {
+                            let bitrate_kbps = config
+                                .audio
+                                .encoder
+                                .bitrate_kbps
+                                .unwrap_or(DEFAULT_BITRATE_KBPS)
+                                .saturating_add_signed(amount as i32 * BITRATE_STEP_KBPS);
+
+                            AppAction::SetBitrate(Some(bitrate_kbps))
+                        },
+                    },
+                    AppAction::SetBitrate(None),
+                ),
+                Option::new(
+                    context.clone(),
+                    "Low Delay Mode".to_owned(),
+                    OptionKind::Switch(config.audio.encoder.mode == EncoderMode::LowDelay),
+                    AppAction::ToggleLowDelay,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Complexity".to_owned(),
+                    OptionKind::Value {
+                        display: |config| config.audio.encoder.complexity.to_string(),
+                        adjust: |config, amount| {
+                            let complexity = config
+                                .audio
+                                .encoder
+                                .complexity
+                                .saturating_add_signed(amount as i8);
+
+                            AppAction::SetComplexity(complexity)
+                        },
+                    },
+                    AppAction::SetComplexity(10),
+                ),
+                Option::new(
+                    context.clone(),
+                    "Error Correction".to_owned(),
+                    OptionKind::Switch(config.audio.encoder.fec),
+                    AppAction::ToggleFec,
+                ),
+                Option::new(
+                    context.clone(),
+                    "Resampler Quality".to_owned(),
+                    OptionKind::Value {
+                        display: |config| format!("{:?}", config.audio.resampler_quality),
+                        adjust: |config, amount| {
+                            let quality = config.audio.resampler_quality;
+
+                            let quality = if amount > 0 {
+                                next(&quality).unwrap_or(quality)
+                            } else {
+                                previous(&quality).unwrap_or(quality)
+                            };
+
+                            AppAction::SetResamplerQuality(quality)
+                        },
+                    },
+                    AppAction::SetResamplerQuality(Default::default()),
+                ),
+                Option::new(
+                    context.clone(),
+                    "Meter Ballistics".to_owned(),
+                    OptionKind::Value {
+                        display: |config| {
+                            match config.audio.meter_ballistics {
+                                MeterBallistics::Vu => "VU",
+                                MeterBallistics::Ppm => "PPM",
+                                MeterBallistics::DigitalPeak => "Peak",
+                            }
+                            .to_owned()
+                        },
+                        adjust: |config, amount| {
+                            let ballistics = config.audio.meter_ballistics;
+
+                            let ballistics = if amount > 0 {
+                                next(&ballistics).unwrap_or(ballistics)
+                            } else {
+                                previous(&ballistics).unwrap_or(ballistics)
+                            };
+
+                            AppAction::SetMeterBallistics(ballistics)
+                        },
+                    },
+                    AppAction::SetMeterBallistics(Default::default()),
+                ),
                 Option::new(
                     context.clone(),
                     "Redo Setup".to_owned(),
@@ -106,8 +260,13 @@ impl View for SettingsModule {
             )
         };
 
+        // Scroll so the selected option is always visible
+        let visible = (block_inner.height as usize).max(1);
+        let offset = (self.selected_option + 1).saturating_sub(visible);
+        let options = self.options.iter().skip(offset).take(visible);
+
         let calculated_constraints: Vec<_> =
-            self.options.iter().map(|_| Constraint::Length(1)).collect();
+            options.clone().map(|_| Constraint::Length(1)).collect();
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -116,8 +275,8 @@ impl View for SettingsModule {
 
         block.render(area, buf);
 
-        for (index, option) in self.options.iter().enumerate() {
-            option.render(chunks[index], buf);
+        for (chunk, option) in chunks.iter().zip(options) {
+            option.render(*chunk, buf);
         }
     }
 
@@ -151,6 +310,13 @@ impl Option {
         }
     }
 
+    fn adjust_if_value(&self, amount: i64) {
+        if let OptionKind::Value { adjust, .. } = &self.kind {
+            let action = adjust(&self.context.config(), amount);
+            self.context.dispatch_action(action);
+        }
+    }
+
     fn focus(&mut self) {
         self.focused = true;
     }
@@ -162,9 +328,19 @@ impl Option {
 
 impl View for Option {
     fn render(&self, area: Rect, buf: &mut Buffer) {
+        let value = match &self.kind {
+            OptionKind::Value { display, .. } => display(&self.context.config()),
+            _ => String::new(),
+        };
+
+        let value_width = (value.len() as u16).max(3);
+
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(area.width - 3), Constraint::Length(3)])
+            .constraints([
+                Constraint::Length(area.width.saturating_sub(value_width)),
+                Constraint::Length(value_width),
+            ])
             .split(area);
 
         let focus_symbol = if self.focused {
@@ -187,17 +363,34 @@ impl View for Option {
 
             paragraph.render(chunks[1], buf);
         }
+
+        if let OptionKind::Value { .. } = &self.kind {
+            Paragraph::new(value)
+                .style(Style::default().fg(Color::DarkGray))
+                .render(chunks[1], buf);
+        }
     }
 
     fn handle_event(&mut self, event: Event) {
         if let Event::Key(key) = event {
-            if key.code == KeyCode::Enter {
-                self.toggle_if_switch();
-                self.context.dispatch_action(self.action.clone());
+            match key.code {
+                KeyCode::Enter => {
+                    self.toggle_if_switch();
+                    self.context.dispatch_action(self.action.clone());
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => self.adjust_if_value(1),
+                KeyCode::Char('-') => self.adjust_if_value(-1),
+                _ => {}
             }
         }
     }
 }
 
+const DELAY_STEP_MS: i64 = 10;
+const BITRATE_STEP_KBPS: i32 = 8;
+
+/// Where adjusting the bitrate starts from when it follows the channel
+const DEFAULT_BITRATE_KBPS: u32 = 64;
+
 const IDLE_SYMBOL: &str = "○";
 const FOCUS_SYMBOL: &str = "●";
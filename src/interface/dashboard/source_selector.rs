@@ -106,12 +106,10 @@ impl View for SourceSelector {
                 .map(|f| f.index() == source.index())
                 .unwrap_or_default();
 
-            let paragraph_area = tui::layout::Rect::new(
-                block_inner.left(),
-                top + index as u16,
-                block_inner.width,
-                1,
-            );
+            let name_width = block_inner.width.saturating_sub(PEAK_METER_WIDTH + 2);
+
+            let paragraph_area =
+                tui::layout::Rect::new(block_inner.left(), top + index as u16, name_width, 1);
 
             let symbol = if !is_discord_ready || !self.focused {
                 IDLE_SYMBOL
@@ -137,6 +135,13 @@ impl View for SourceSelector {
                 .style(Style::default().fg(color));
 
             paragraph.render(paragraph_area, buf);
+
+            if source.available() && name_width > 0 {
+                let peak = self.context.source_peak(source);
+                let meter_x = block_inner.left() + name_width + 1;
+
+                render_peak_meter(peak, meter_x, paragraph_area.y, buf);
+            }
         }
     }
 
@@ -161,6 +166,44 @@ impl View for SourceSelector {
     }
 }
 
+/// Draws a small bar showing how loud a source is, so it's easy to tell which ones are making sound
+fn render_peak_meter(peak: f32, x: u16, y: u16, buf: &mut Buffer) {
+    let db = 20. * peak.max(f32::EPSILON).log10();
+    let width = PEAK_METER_WIDTH as f32 * (1. - db / PEAK_METER_MIN_DB).clamp(0., 1.);
+
+    let color = if db >= -6. {
+        Color::Rgb(199, 54, 28)
+    } else if db >= -18. {
+        Color::Rgb(255, 240, 85)
+    } else {
+        Color::Rgb(82, 224, 45)
+    };
+
+    for cell in 0..PEAK_METER_WIDTH {
+        let fill = (width - cell as f32).clamp(0., 1.);
+
+        let (symbol, style) = if fill > 0. {
+            let partial = (fill * (BAR_PARTIALS.len() - 1) as f32).ceil() as usize;
+            (BAR_PARTIALS[partial], Style::default().fg(color))
+        } else {
+            (
+                PEAK_METER_EMPTY_SYMBOL,
+                Style::default().fg(Color::DarkGray),
+            )
+        };
+
+        buf.set_string(x + cell, y, symbol, style);
+    }
+}
+
+/// How many cells the peak meters take up
+const PEAK_METER_WIDTH: u16 = 6;
+/// The lowest level the peak meters show, in dBFS
+const PEAK_METER_MIN_DB: f32 = -60.;
+const PEAK_METER_EMPTY_SYMBOL: &str = "·";
+
+const BAR_PARTIALS: [&str; 9] = [" ", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
+
 const IDLE_SYMBOL: &str = "○";
 const HOVER_SYMBOL: &str = "●";
 const ACTIVE_SYMBOL: &str = "►";
@@ -0,0 +1,85 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, Widget},
+};
+
+use crate::{app::AppContext, interface::View};
+
+/// Shows the frequency content of the stream as bars, from low to high frequencies
+pub struct Spectrum {
+    context: AppContext,
+}
+
+impl Spectrum {
+    pub fn new(context: AppContext) -> Self {
+        Self { context }
+    }
+}
+
+impl View for Spectrum {
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title("─ Spectrum ")
+            .borders(Borders::all());
+
+        let inner = block.inner(area);
+        let inner = Rect::new(
+            inner.left() + 1,
+            inner.top(),
+            inner.width.saturating_sub(2),
+            inner.height,
+        );
+
+        block.render(area, buf);
+
+        let bands = self.context.spectrum();
+
+        if bands.is_empty() || inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let height = inner.height as f32;
+
+        for column in 0..inner.width {
+            let band = bands[column as usize * bands.len() / inner.width as usize];
+
+            let level = band.level_ranged() * height;
+            let peak_row = ((band.peak_ranged() * height).ceil() as u16).min(inner.height);
+
+            for row in 0..inner.height {
+                let fill = (level - row as f32).clamp(0., 1.);
+                let y = inner.bottom() - 1 - row;
+
+                let color = bar_color(row as f32 / height);
+                let symbol = BAR_PARTIALS[(fill * (BAR_PARTIALS.len() - 1) as f32) as usize];
+
+                let is_peak = row + 1 == peak_row && fill < 1.;
+
+                let (symbol, color) = if is_peak && fill == 0. {
+                    (PEAK_SYMBOL, Color::Gray)
+                } else {
+                    (symbol, color)
+                };
+
+                buf.set_string(inner.x + column, y, symbol, Style::default().fg(color));
+            }
+        }
+    }
+}
+
+/// Colors the top of the range like the meter does
+fn bar_color(position: f32) -> Color {
+    if position >= 0.9 {
+        Color::Rgb(199, 54, 28)
+    } else if position >= 0.6 {
+        Color::Rgb(255, 240, 85)
+    } else {
+        Color::Rgb(82, 224, 45)
+    }
+}
+
+const BAR_PARTIALS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+const PEAK_SYMBOL: &str = "▔";
@@ -1,6 +1,9 @@
+use enum_iterator::Sequence;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::io::Write;
+use std::time::Duration;
 use std::{fs::File, io::Read};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -9,7 +12,24 @@ pub struct Config {
     pub user_id: u64,
 
     pub show_meter: bool,
+    #[serde(default)]
+    pub show_spectrum: bool,
+    #[serde(default)]
+    pub show_scope: bool,
+    #[serde(default)]
+    pub show_history: bool,
     pub screen_share_only: bool,
+
+    #[serde(default)]
+    pub audio: AudioConfig,
+
+    /// Settings remembered for each application, keyed by its binary
+    #[serde(default)]
+    pub profiles: HashMap<String, SourceProfile>,
+
+    /// The application whose profile is laid over the audio settings
+    #[serde(skip)]
+    pub active_profile: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,7 +37,147 @@ pub struct ReadOnlyConfig {
     pub bot_token: String,
     pub user_id: u64,
     pub show_meter: bool,
+    pub show_spectrum: bool,
+    pub show_scope: bool,
+    pub show_history: bool,
     pub screen_share_only: bool,
+    pub audio: AudioConfig,
+}
+
+/// Settings for the audio pipeline
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// How long it takes for a stream to fade in when it starts, in milliseconds
+    pub fade_in_ms: u64,
+    /// How long it takes for a stream to fade out when it stops, in milliseconds
+    pub fade_out_ms: u64,
+
+    /// Mix the previous and the next source when switching, instead of fading one out before the other
+    pub crossfade: bool,
+    /// How long a crossfade lasts, in milliseconds
+    pub crossfade_ms: u64,
+
+    pub ducking: DuckingConfig,
+
+    pub agc: AgcConfig,
+
+    /// Delays the stream to sync it up with screen share, in milliseconds
+    pub delay_ms: u64,
+
+    pub encoder: EncoderConfig,
+
+    /// The effects the stream goes through, in order
+    pub effects: Vec<EffectConfig>,
+
+    /// How well sources that don't run at 48 kHz are resampled
+    pub resampler_quality: ResamplerQuality,
+
+    /// How the level meter responds to the signal
+    pub meter_ballistics: MeterBallistics,
+}
+
+/// Settings that are applied whenever a source from an application is selected
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SourceProfile {
+    pub delay_ms: u64,
+    /// The normalize, gain and filter effects as they were set for the application
+    pub effects: Vec<EffectConfig>,
+}
+
+/// Settings for lowering the stream while people in the voice channel are talking
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DuckingConfig {
+    pub enabled: bool,
+    /// How much the stream is lowered, in dB
+    pub amount_db: f32,
+    /// Only duck when the user the bot follows is talking
+    pub target_user_only: bool,
+    /// How long it takes to lower the stream, in milliseconds
+    pub attack_ms: u64,
+    /// How long it takes to bring the stream back up, in milliseconds
+    pub release_ms: u64,
+}
+
+/// Settings for automatically adjusting the input gain of sources that are too hot or too quiet
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AgcConfig {
+    pub enabled: bool,
+    /// The window the peaks of the processed signal are kept in, in dBFS
+    pub min_peak_db: f32,
+    pub max_peak_db: f32,
+    /// The most the input can be raised, in dB
+    pub max_boost_db: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default, Sequence)]
+pub enum ResamplerQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default, Sequence)]
+pub enum MeterBallistics {
+    /// Averages over 300 ms, close to how loud things sound
+    Vu,
+    /// A quasi-peak programme meter as used by the EBU and BBC, with a slow fall
+    Ppm,
+    /// Instant attack and a fall of 20 dB in 1.7 s
+    #[default]
+    DigitalPeak,
+}
+
+/// An effect in the processing chain
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct EffectConfig {
+    pub kind: EffectKind,
+    #[serde(default)]
+    pub bypassed: bool,
+    /// Parameter values by name, missing ones use the effect's defaults
+    #[serde(default)]
+    pub parameters: HashMap<String, f32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum EffectKind {
+    /// Makes up for the volume of the source
+    Normalize,
+    Gain,
+    Filter,
+    Limiter,
+    /// A LADSPA plugin, by the path of its library and its label.
+    /// Relative paths are searched for in LADSPA_PATH.
+    Ladspa {
+        path: String,
+        label: String,
+    },
+}
+
+/// Settings for encoding the stream before it is sent to Discord
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct EncoderConfig {
+    /// The bitrate in kbps, capped to what the voice channel allows.
+    /// Uses the highest bitrate the channel allows when unset.
+    pub bitrate_kbps: Option<u32>,
+    pub mode: EncoderMode,
+    /// How much effort goes into encoding, from 0 to 10
+    pub complexity: u8,
+    /// In-band forward error correction, which helps with packet loss at the cost of bitrate
+    pub fec: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum EncoderMode {
+    /// Tuned for music and other non-voice audio
+    Audio,
+    /// Trades quality for the lowest possible latency
+    LowDelay,
 }
 
 impl Config {
@@ -34,7 +194,13 @@ impl Config {
             bot_token,
             user_id,
             show_meter: true,
+            show_spectrum: false,
+            show_scope: false,
+            show_history: false,
             screen_share_only: false,
+            audio: Default::default(),
+            profiles: Default::default(),
+            active_profile: None,
         }
     }
 
@@ -64,7 +230,262 @@ impl Config {
             bot_token: self.bot_token.clone(),
             user_id: self.user_id,
             show_meter: self.show_meter,
+            show_spectrum: self.show_spectrum,
+            show_scope: self.show_scope,
+            show_history: self.show_history,
             screen_share_only: self.screen_share_only,
+            audio: self.effective_audio(),
+        }
+    }
+
+    /// The audio settings with the active profile laid over them
+    pub fn effective_audio(&self) -> AudioConfig {
+        let mut audio = self.audio.clone();
+
+        let profile = self
+            .active_profile
+            .as_ref()
+            .and_then(|application| self.profiles.get(application));
+
+        if let Some(profile) = profile {
+            profile.apply(&mut audio);
+        }
+
+        audio
+    }
+
+    /// Edits the audio settings as they are heard.
+    /// With a profile active, changes to the per-application settings are saved in it and the rest in the base settings.
+    pub fn edit_audio(&mut self, cb: impl FnOnce(&mut AudioConfig)) {
+        let application = match self.active_profile.clone() {
+            Some(application) => application,
+            None => return cb(&mut self.audio),
+        };
+
+        let mut audio = self.effective_audio();
+        let before = SourceProfile::from_audio(&audio);
+        cb(&mut audio);
+
+        // Until the user changes one of its own settings, the application keeps following the base settings
+        if SourceProfile::from_audio(&audio) != before {
+            self.profiles
+                .entry(application)
+                .or_default()
+                .remember(&audio);
+        }
+
+        let base = std::mem::replace(&mut self.audio, audio);
+        SourceProfile::from_audio(&base).apply(&mut self.audio);
+    }
+}
+
+impl SourceProfile {
+    /// Applies the profile over the audio config
+    pub fn apply(&self, audio: &mut AudioConfig) {
+        audio.delay_ms = self.delay_ms;
+
+        for (index, saved) in self.effects.iter().enumerate() {
+            // The nth saved effect of a kind belongs to the nth effect of that kind in the chain
+            let occurrence = self.effects[..index]
+                .iter()
+                .filter(|e| e.kind == saved.kind)
+                .count();
+
+            let effect = audio
+                .effects
+                .iter_mut()
+                .filter(|e| e.kind == saved.kind)
+                .nth(occurrence);
+
+            if let Some(effect) = effect {
+                effect.bypassed = saved.bypassed;
+                effect.parameters = saved.parameters.clone();
+            }
+        }
+    }
+
+    fn from_audio(audio: &AudioConfig) -> Self {
+        let mut profile = Self::default();
+        profile.remember(audio);
+        profile
+    }
+
+    /// Takes the per-application settings from the audio config
+    pub fn remember(&mut self, audio: &AudioConfig) {
+        self.delay_ms = audio.delay_ms;
+        self.effects = audio
+            .effects
+            .iter()
+            .filter(|e| e.kind.is_per_application())
+            .cloned()
+            .collect();
+    }
+}
+
+impl EffectKind {
+    /// Whether the effect is saved in a [SourceProfile]
+    fn is_per_application(&self) -> bool {
+        matches!(self, Self::Normalize | Self::Gain | Self::Filter)
+    }
+}
+
+impl AudioConfig {
+    pub fn fade_in(&self) -> Duration {
+        Duration::from_millis(self.fade_in_ms)
+    }
+
+    pub fn fade_out(&self) -> Duration {
+        Duration::from_millis(self.fade_out_ms)
+    }
+
+    pub fn crossfade(&self) -> Duration {
+        Duration::from_millis(self.crossfade_ms)
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            fade_in_ms: 30,
+            fade_out_ms: 30,
+            crossfade: false,
+            crossfade_ms: 1500,
+            ducking: Default::default(),
+            agc: Default::default(),
+            delay_ms: 0,
+            encoder: Default::default(),
+            effects: vec![
+                EffectConfig::new(EffectKind::Normalize, false),
+                EffectConfig::new(EffectKind::Gain, false),
+                EffectConfig::new(EffectKind::Filter, true),
+                EffectConfig::new(EffectKind::Limiter, true),
+            ],
+            resampler_quality: Default::default(),
+            meter_ballistics: Default::default(),
+        }
+    }
+}
+
+impl EffectConfig {
+    pub fn new(kind: EffectKind, bypassed: bool) -> Self {
+        Self {
+            kind,
+            bypassed,
+            parameters: Default::default(),
+        }
+    }
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self {
+            bitrate_kbps: None,
+            mode: EncoderMode::Audio,
+            complexity: 10,
+            fec: false,
+        }
+    }
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_peak_db: -9.,
+            max_peak_db: -3.,
+            max_boost_db: 12.,
         }
     }
 }
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            amount_db: 12.,
+            target_user_only: false,
+            attack_ms: 50,
+            release_ms: 600,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPLICATION: &str = "mpv";
+
+    fn config_with_profile_active() -> Config {
+        let mut config = Config::new(String::new(), 0);
+        config.active_profile = Some(APPLICATION.to_string());
+        config
+    }
+
+    fn set_parameter(audio: &mut AudioConfig, kind: EffectKind, name: &str, value: f32) {
+        let effect = audio.effects.iter_mut().find(|e| e.kind == kind).unwrap();
+        effect.parameters.insert(name.to_string(), value);
+    }
+
+    #[test]
+    fn edits_with_a_profile_active_leave_the_base_alone() {
+        let mut config = config_with_profile_active();
+        let base = config.audio.clone();
+
+        config.edit_audio(|audio| {
+            audio.delay_ms = 300;
+            set_parameter(audio, EffectKind::Gain, "Gain", 6.);
+        });
+
+        assert_eq!(config.audio, base);
+        assert_eq!(config.profiles[APPLICATION].delay_ms, 300);
+
+        let effective = config.effective_audio();
+        assert_eq!(effective.delay_ms, 300);
+        assert_eq!(effective.effects[1].parameters["Gain"], 6.);
+    }
+
+    #[test]
+    fn shared_edits_go_to_the_base_without_creating_a_profile() {
+        let mut config = config_with_profile_active();
+
+        config.edit_audio(|audio| audio.effects[3].bypassed = false);
+
+        assert!(config.profiles.is_empty());
+        assert!(!config.audio.effects[3].bypassed);
+    }
+
+    #[test]
+    fn effective_audio_is_the_base_without_an_active_profile() {
+        let mut config = config_with_profile_active();
+        config.edit_audio(|audio| audio.delay_ms = 300);
+        config.active_profile = None;
+
+        assert_eq!(config.effective_audio(), config.audio);
+    }
+
+    #[test]
+    fn profiles_apply_to_every_instance_of_an_effect() {
+        let mut audio = AudioConfig::default();
+        audio
+            .effects
+            .push(EffectConfig::new(EffectKind::Filter, true));
+
+        let mut edited = audio.clone();
+        edited.effects[2]
+            .parameters
+            .insert("Low Cut".to_string(), 100.);
+        edited.effects[4].bypassed = false;
+        edited.effects[4]
+            .parameters
+            .insert("High Cut".to_string(), 8000.);
+
+        SourceProfile::from_audio(&edited).apply(&mut audio);
+
+        assert_eq!(audio.effects, edited.effects);
+    }
+}